@@ -0,0 +1,253 @@
+use crate::error::Warnings;
+use crate::source::video::seek_input;
+use crate::types::consts::{EAGAIN, NO_PTS};
+use anyhow::{Context, Result, anyhow};
+use ffmpeg::format::{Sample, sample::Type as SampleType};
+use ffmpeg::software::resampling;
+use ffmpeg_next as ffmpeg;
+use rodio::source::{SeekError, Source};
+use std::{path::Path, time::Duration};
+
+/// Best audio stream of any container ffmpeg can read, as interleaved `f32` samples
+///
+/// The stream keeps its sample rate (rodio converts it for the output device); layouts with
+/// more than two channels are downmixed to stereo so dialog in center channels isn't lost.
+pub struct AudioSource {
+    input: ffmpeg::format::context::Input,
+    decoder: ffmpeg::codec::decoder::Audio,
+    stream_index: usize,
+    time_base: f64,
+    start_pts: i64,
+    duration: Option<Duration>,
+    channels: u16,
+    sample_rate: u32,
+    /// Created from the first decoded frame and rebuilt if the stream changes format
+    resampler: Option<resampling::Context>,
+    decoded: ffmpeg::frame::Audio,
+    samples: Vec<f32>,
+    position: usize,
+    /// Samples before this time are dropped, since seeking lands on an earlier packet
+    seek_target: Option<f64>,
+    eof_sent: bool,
+    finished: bool,
+    /// Told when decoding stops early
+    warnings: Warnings,
+}
+
+impl AudioSource {
+    pub fn open(path: &Path, warnings: Warnings) -> Result<Self> {
+        ffmpeg::init().context("Failed to initialize FFmpeg")?;
+
+        let input = ffmpeg::format::input(&path)
+            .with_context(|| format!("Failed to open audio file: {path:?}"))?;
+
+        let input_stream = input
+            .streams()
+            .best(ffmpeg::media::Type::Audio)
+            .ok_or_else(|| anyhow!("Could not find audio stream in {path:?}"))?;
+        let stream_index = input_stream.index();
+        let codec_parameters = input_stream.parameters();
+
+        let decoder = ffmpeg::codec::context::Context::from_parameters(codec_parameters)?
+            .decoder()
+            .audio()?;
+        let time_base: f64 = input_stream.time_base().into();
+        let start_pts = match input_stream.start_time() {
+            NO_PTS => 0,
+            pts => pts,
+        };
+        let duration = match input_stream.duration() {
+            NO_PTS | 0 => None,
+            duration => Some(Duration::from_secs_f64(duration as f64 * time_base)),
+        };
+
+        if decoder.rate() == 0 || decoder.channels() == 0 {
+            return Err(anyhow!(
+                "Audio stream has no sample rate or channels: {path:?}"
+            ));
+        }
+
+        Ok(Self {
+            input,
+            stream_index,
+            time_base,
+            start_pts,
+            duration,
+            channels: decoder.channels().min(2),
+            sample_rate: decoder.rate(),
+            decoder,
+            resampler: None,
+            decoded: ffmpeg::frame::Audio::empty(),
+            samples: Vec::new(),
+            position: 0,
+            seek_target: None,
+            eof_sent: false,
+            finished: false,
+            warnings,
+        })
+    }
+
+    /// Decodes the next frame into `samples`, returning `false` once the stream has ended
+    fn refill(&mut self) -> Result<bool> {
+        loop {
+            match self.decoder.receive_frame(&mut self.decoded) {
+                Ok(()) => {
+                    self.resample()?;
+                    if !self.samples.is_empty() {
+                        return Ok(true);
+                    }
+                    continue;
+                }
+                Err(ffmpeg::Error::Eof) => return Ok(false),
+                Err(ffmpeg::Error::Other { errno }) if errno == EAGAIN => {}
+                Err(_) if self.eof_sent => return Ok(false),
+                // Corrupt packets only cost a few milliseconds of sound
+                Err(_) => {}
+            }
+
+            if self.eof_sent {
+                return Ok(false);
+            }
+            self.feed_packet()?;
+        }
+    }
+
+    /// Sends the next packet of the audio stream to the decoder, or EOF when there are none left
+    fn feed_packet(&mut self) -> Result<()> {
+        loop {
+            let Some((stream, packet)) = self.input.packets().next() else {
+                let _ = self.decoder.send_eof();
+                self.eof_sent = true;
+                return Ok(());
+            };
+            if stream.index() != self.stream_index {
+                continue;
+            }
+
+            return match self.decoder.send_packet(&packet) {
+                Ok(()) | Err(ffmpeg::Error::Other { .. }) => Ok(()),
+                Err(e) => Err(anyhow!("Failed to send packet to audio decoder: {}", e)),
+            };
+        }
+    }
+
+    /// Converts the decoded frame to interleaved `f32`, trimming anything before a seek target
+    fn resample(&mut self) -> Result<()> {
+        let frame = &self.decoded;
+        let input_layout = if frame.channel_layout().is_empty() {
+            ffmpeg::ChannelLayout::default(frame.channels().into())
+        } else {
+            frame.channel_layout()
+        };
+
+        let stale = self.resampler.as_ref().is_none_or(|resampler| {
+            let input = resampler.input();
+            input.format != frame.format()
+                || input.rate != frame.rate()
+                || input.channel_layout.channels() != input_layout.channels()
+        });
+        if stale {
+            let output_layout = ffmpeg::ChannelLayout::default(self.channels.into());
+            self.resampler = Some(
+                resampling::Context::get(
+                    frame.format(),
+                    input_layout,
+                    frame.rate(),
+                    Sample::F32(SampleType::Packed),
+                    output_layout,
+                    self.sample_rate,
+                )
+                .context("Failed to create audio resampler")?,
+            );
+        }
+        let Some(resampler) = self.resampler.as_mut() else {
+            return Ok(());
+        };
+
+        let mut converted = ffmpeg::frame::Audio::empty();
+        resampler
+            .run(frame, &mut converted)
+            .context("Failed to resample audio")?;
+
+        let sample_count = converted.samples() * self.channels as usize;
+        let bytes = &converted.data(0)[..sample_count * size_of::<f32>()];
+        self.samples.clear();
+        self.samples.extend(
+            bytes
+                .chunks_exact(size_of::<f32>())
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]])),
+        );
+        self.position = 0;
+
+        if let Some(target) = self.seek_target {
+            let time = match frame.timestamp().or(frame.pts()) {
+                Some(pts) => (pts - self.start_pts) as f64 * self.time_base,
+                None => target,
+            };
+            let early_frames = ((target - time) * self.sample_rate as f64).max(0.0) as usize;
+            self.position = (early_frames * self.channels as usize).min(self.samples.len());
+            if self.position < self.samples.len() {
+                self.seek_target = None;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for AudioSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        while self.position >= self.samples.len() {
+            if self.finished {
+                return None;
+            }
+            match self.refill() {
+                Ok(true) => {}
+                Ok(false) => self.finished = true,
+                Err(e) => {
+                    self.warnings
+                        .warn(format_args!("Audio decoding stopped: {e:#}"));
+                    self.finished = true;
+                }
+            }
+        }
+
+        let sample = self.samples[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for AudioSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let target = pos.as_secs_f64();
+        let absolute = target + self.start_pts as f64 * self.time_base;
+        seek_input(&mut self.input, absolute).map_err(|e| SeekError::Other(Box::new(e)))?;
+
+        self.decoder.flush();
+        self.samples.clear();
+        self.position = 0;
+        self.seek_target = Some(target);
+        self.eof_sent = false;
+        self.finished = false;
+        Ok(())
+    }
+}
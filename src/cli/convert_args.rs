@@ -0,0 +1,55 @@
+use super::render_args::RenderArgs;
+use ascii4::{AudioFormat, Converter, DEFAULT_CONVERT_FPS, OutputFormat};
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+pub struct ConvertArgs {
+    /// Input video, animated GIF, APNG or WebP, image directory or glob pattern, frames
+    /// converted earlier, or `-` for a video on stdin
+    #[arg(short, long)]
+    pub input: String,
+
+    /// Output directory for ASCII frames, or file path with `--format pack` or `cast`
+    #[arg(short, long, default_value = "output")]
+    pub output_dir: String,
+
+    /// Target FPS for ASCII conversion
+    #[arg(short, long, default_value_t = DEFAULT_CONVERT_FPS)]
+    pub fps: f64,
+
+    /// Number of worker threads for ASCII mapping and writing (defaults to available cores)
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+
+    #[command(flatten)]
+    pub render: RenderArgs,
+
+    /// Output layout: per-second or flat frame files, a pack, stdout or an asciicast recording
+    #[arg(long, value_enum, default_value_t = OutputFormat::Dirs)]
+    pub format: OutputFormat,
+
+    /// Compress frames with zstd (only with `--format pack`)
+    #[arg(long)]
+    pub compress: bool,
+
+    /// Transcode the input's audio track next to the frames, where `play` picks it up
+    #[arg(long, value_enum)]
+    pub audio: Option<AudioFormat>,
+}
+
+impl From<ConvertArgs> for Converter {
+    fn from(args: ConvertArgs) -> Self {
+        let mut converter = Converter::new(args.input, args.output_dir)
+            .fps(args.fps)
+            .render(args.render.into())
+            .format(args.format)
+            .compress(args.compress);
+        if let Some(jobs) = args.jobs {
+            converter = converter.jobs(jobs);
+        }
+        if let Some(audio) = args.audio {
+            converter = converter.audio(audio);
+        }
+        converter
+    }
+}
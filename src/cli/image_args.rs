@@ -0,0 +1,18 @@
+use super::render_args::RenderArgs;
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+pub struct ImageArgs {
+    /// Image files, directories of images or glob patterns such as "photos/*.png"
+    #[arg(short, long, required = true, num_args = 1..)]
+    pub input: Vec<String>,
+
+    /// Output file, or output directory when converting several images or when it exists
+    /// (stdout if omitted)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub render: RenderArgs,
+}
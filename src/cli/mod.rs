@@ -0,0 +1,5 @@
+pub mod convert_args;
+pub mod image_args;
+pub mod play_args;
+pub mod render_args;
+pub mod tone_args;
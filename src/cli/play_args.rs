@@ -0,0 +1,66 @@
+use super::render_args::RenderArgs;
+use ascii4::Player;
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+pub struct PlayArgs {
+    /// Directory containing ASCII frames (organized in second subdirectories) or a packed container file
+    #[arg(short, long, default_value = "output")]
+    pub frames_dir: PathBuf,
+
+    /// Video, animated image, image directory or glob pattern, or `-` for a video on stdin, to
+    /// render on the fly instead of frames; a video plays with its own audio track
+    #[arg(long, conflicts_with = "frames_dir")]
+    pub input: Option<PathBuf>,
+
+    /// Playback FPS (defaults to the FPS recorded by `convert` or of the video, or 30)
+    #[arg(short, long)]
+    pub fps: Option<f64>,
+
+    /// Optional path to audio file or video file containing audio track
+    #[arg(
+        short,
+        long,
+        help = "Path to audio file or video file with audio track"
+    )]
+    pub audio: Option<PathBuf>,
+
+    /// Loop the animation and audio like a GIF
+    #[arg(short = 'g', long = "gif", default_value_t = false)]
+    pub loop_gif: bool,
+
+    /// Sync audio with animation loop (requires --gif)
+    #[arg(
+        short,
+        long,
+        requires = "loop_gif",
+        help = "Restart audio with each animation loop (requires --gif)"
+    )]
+    pub sync: bool,
+
+    /// Show an info overlay with the frame counter, FPS and character ramp
+    #[arg(short, long)]
+    pub info: bool,
+
+    // Rendering options for sources that aren't rendered yet; the size defaults to the terminal
+    #[command(flatten)]
+    pub render: RenderArgs,
+}
+
+impl From<PlayArgs> for Player {
+    fn from(args: PlayArgs) -> Self {
+        let mut player = Player::new(args.input.unwrap_or(args.frames_dir))
+            .looping(args.loop_gif)
+            .sync(args.sync)
+            .info(args.info)
+            .render(args.render.into());
+        if let Some(fps) = args.fps {
+            player = player.fps(fps);
+        }
+        if let Some(audio) = args.audio {
+            player = player.audio(audio);
+        }
+        player
+    }
+}
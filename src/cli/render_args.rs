@@ -0,0 +1,68 @@
+use super::tone_args::ToneArgs;
+use ascii4::{Charset, ColorMode, Dither, FitMode, RenderMode, RenderOptions};
+use clap::Args;
+
+/// Sizing and glyph options shared by every command that renders frames
+#[derive(Args, Clone, Debug)]
+pub struct RenderArgs {
+    /// Output ASCII art width (derived from the height and aspect ratio if omitted)
+    #[arg(short = 'W', long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: Option<u32>,
+
+    /// Output ASCII art height (derived from the width and aspect ratio if omitted)
+    #[arg(short = 'H', long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: Option<u32>,
+
+    /// Bound the output by the terminal size when neither width nor height is given
+    #[arg(short = 'A', long)]
+    pub auto_size: bool,
+
+    /// How to map the image onto the output when both dimensions are bounded
+    #[arg(long, value_enum, default_value_t = FitMode::Fit)]
+    pub fit: FitMode,
+
+    /// Height of a terminal character cell divided by its width
+    #[arg(long, default_value_t = 2.0)]
+    pub cell_aspect: f64,
+
+    /// Color escape sequences to embed in every frame
+    #[arg(short, long, value_enum, default_value_t = ColorMode::None)]
+    pub color: ColorMode,
+
+    /// Glyph strategy used to draw each frame
+    #[arg(short, long, value_enum, default_value_t = RenderMode::Ascii)]
+    pub mode: RenderMode,
+
+    /// Character ramp: a preset (simple, detailed, blocks, digits, inverted) or a literal string
+    #[arg(long, default_value = "detailed")]
+    pub charset: Charset,
+
+    /// Reverse the character ramp for terminals with a light background
+    #[arg(long)]
+    pub invert: bool,
+
+    /// Dither between ramp glyphs, and between palette colors with `--color 16` or `256`
+    #[arg(long, value_enum, default_value_t = Dither::None)]
+    pub dither: Dither,
+
+    #[command(flatten)]
+    pub tone: ToneArgs,
+}
+
+impl From<RenderArgs> for RenderOptions {
+    fn from(args: RenderArgs) -> Self {
+        Self {
+            width: args.width,
+            height: args.height,
+            auto_size: args.auto_size,
+            fit: args.fit,
+            cell_aspect: args.cell_aspect,
+            color: args.color,
+            mode: args.mode,
+            charset: args.charset,
+            invert: args.invert,
+            dither: args.dither,
+            tone: args.tone.into(),
+        }
+    }
+}
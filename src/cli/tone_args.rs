@@ -0,0 +1,42 @@
+use ascii4::{AutoLevels, ToneOptions};
+use clap::Args;
+
+/// Tone adjustments applied to the pixels of every frame before glyphs are picked
+///
+/// The default leaves frames untouched. Levels are adjusted first, then brightness, contrast,
+/// gamma and saturation.
+#[derive(Args, Clone, Copy, Debug)]
+pub struct ToneArgs {
+    /// Brightness offset, from -1.0 (everything black) to 1.0 (everything white)
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub brightness: f32,
+
+    /// Contrast factor around mid gray: 0 is flat gray, above 1 is punchier
+    #[arg(long, default_value_t = 1.0)]
+    pub contrast: f32,
+
+    /// Gamma correction: above 1 lifts shadows and midtones, below 1 darkens them
+    #[arg(long, default_value_t = 1.0)]
+    pub gamma: f32,
+
+    /// Saturation factor: 0 is grayscale, above 1 more vivid
+    #[arg(long, default_value_t = 1.0)]
+    pub saturation: f32,
+
+    /// Spread every frame's tones over the full range, by stretching them or equalizing the
+    /// histogram
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "stretch")]
+    pub auto_levels: Option<AutoLevels>,
+}
+
+impl From<ToneArgs> for ToneOptions {
+    fn from(args: ToneArgs) -> Self {
+        Self {
+            brightness: args.brightness,
+            contrast: args.contrast,
+            gamma: args.gamma,
+            saturation: args.saturation,
+            auto_levels: args.auto_levels,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use rodio::Sink;
+use std::time::{Duration, Instant};
+
+/// Master clock the playback schedule is derived from, in animation time
+///
+/// While audio is playing its position is authoritative; a monotonic clock is kept anchored
+/// to it so time keeps flowing smoothly once the soundtrack ends or when there is no audio at
+/// all. The sink reports positions in played-back time, so after every speed change or seek
+/// the audio is re-seeked to keep `position * speed` equal to the animation time.
+pub struct PlaybackClock<'a> {
+    audio: Option<&'a Sink>,
+    /// Animation time at `anchor`
+    base: Duration,
+    anchor: Instant,
+    speed: f64,
+    paused: bool,
+}
+
+impl<'a> PlaybackClock<'a> {
+    pub fn new(audio: Option<&'a Sink>) -> Self {
+        Self {
+            audio,
+            base: Duration::ZERO,
+            anchor: Instant::now(),
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    /// Current animation time
+    pub fn now(&mut self) -> Duration {
+        if self.paused {
+            return self.base;
+        }
+
+        match self.audio {
+            Some(sink) if !sink.empty() => {
+                self.base = sink.get_pos().mul_f64(self.speed);
+                self.anchor = Instant::now();
+                self.base
+            }
+            _ => self.base + self.anchor.elapsed().mul_f64(self.speed),
+        }
+    }
+
+    /// Restarts the clock from zero, e.g. when audio is reloaded for a new loop
+    pub fn restart(&mut self) {
+        self.base = Duration::ZERO;
+        self.anchor = Instant::now();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn pause(&mut self) {
+        self.base = self.now();
+        self.paused = true;
+        if let Some(sink) = self.audio {
+            sink.pause();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.anchor = Instant::now();
+        self.paused = false;
+        if let Some(sink) = self.audio {
+            sink.play();
+        }
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        let now = self.now();
+        self.speed = speed;
+        if let Some(sink) = self.audio {
+            sink.set_speed(speed as f32);
+        }
+        self.seek(now);
+    }
+
+    /// Jumps to an animation time, moving the audio along when possible
+    pub fn seek(&mut self, time: Duration) {
+        self.base = time;
+        self.anchor = Instant::now();
+
+        if let Some(sink) = self.audio
+            && sink.try_seek(time.div_f64(self.speed)).is_err()
+        {
+            // Audio that can't follow would drag the schedule along; let it play on unsynced
+            self.audio = None;
+        }
+    }
+}
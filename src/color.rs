@@ -0,0 +1,196 @@
+use crate::color_mode::ColorMode;
+use image::Rgb;
+use std::fmt::Write;
+
+/// Resets all SGR attributes
+pub const SGR_RESET: &str = "\x1b[0m";
+
+/// Channel levels of the 6x6x6 cube in the xterm 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Default xterm values of the 16 basic colors, in SGR order
+const BASIC_COLORS: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// Terminal color a pixel is quantized to for a given color mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TermColor {
+    Rgb(u8, u8, u8),
+    Indexed(u8),
+    Basic(u8),
+}
+
+impl TermColor {
+    /// Quantizes a pixel, returning `None` when the mode carries no color
+    pub fn from_pixel(pixel: &Rgb<u8>, mode: ColorMode) -> Option<Self> {
+        let [r, g, b] = pixel.0;
+        match mode {
+            ColorMode::Truecolor => Some(Self::Rgb(r, g, b)),
+            ColorMode::Ansi256 => Some(Self::Indexed(nearest_256(r, g, b))),
+            ColorMode::Ansi16 => Some(Self::Basic(nearest_16(r, g, b))),
+            ColorMode::None => None,
+        }
+    }
+
+    /// Color a terminal with the default xterm palette shows for this value
+    pub fn rgb(&self) -> [u8; 3] {
+        match *self {
+            Self::Rgb(r, g, b) => [r, g, b],
+            Self::Indexed(index @ 0..16) | Self::Basic(index) => BASIC_COLORS[index as usize % 16],
+            Self::Indexed(index @ 16..232) => {
+                let cube = index as usize - 16;
+                [cube / 36, cube / 6 % 6, cube % 6].map(|level| CUBE_LEVELS[level])
+            }
+            Self::Indexed(index) => [8 + (index - 232) * 10; 3],
+        }
+    }
+
+    /// Appends the SGR sequence selecting this color as the foreground
+    pub fn write_fg(&self, out: &mut String) {
+        let _ = match *self {
+            Self::Rgb(r, g, b) => write!(out, "\x1b[38;2;{r};{g};{b}m"),
+            Self::Indexed(index) => write!(out, "\x1b[38;5;{index}m"),
+            Self::Basic(index) if index < 8 => write!(out, "\x1b[{}m", 30 + index),
+            Self::Basic(index) => write!(out, "\x1b[{}m", 90 + index - 8),
+        };
+    }
+
+    /// Appends the SGR sequence selecting this color as the background
+    pub fn write_bg(&self, out: &mut String) {
+        let _ = match *self {
+            Self::Rgb(r, g, b) => write!(out, "\x1b[48;2;{r};{g};{b}m"),
+            Self::Indexed(index) => write!(out, "\x1b[48;5;{index}m"),
+            Self::Basic(index) if index < 8 => write!(out, "\x1b[{}m", 40 + index),
+            Self::Basic(index) => write!(out, "\x1b[{}m", 100 + index - 8),
+        };
+    }
+}
+
+/// Typical gap between neighbouring palette colors in one channel, `None` for modes that
+/// don't quantize colors
+pub fn palette_step(mode: ColorMode) -> Option<f32> {
+    match mode {
+        ColorMode::Ansi256 => Some(40.0),
+        ColorMode::Ansi16 => Some(128.0),
+        ColorMode::Truecolor | ColorMode::None => None,
+    }
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(&x, &y)| (x as i32 - y as i32).pow(2) as u32)
+        .sum()
+}
+
+fn nearest_cube_level(value: u8) -> usize {
+    CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, level)| (**level as i32 - value as i32).abs())
+        .map_or(0, |(index, _)| index)
+}
+
+/// Closest xterm 256-color index, choosing between the color cube and the gray ramp
+pub fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let (ri, gi, bi) = (
+        nearest_cube_level(r),
+        nearest_cube_level(g),
+        nearest_cube_level(b),
+    );
+    let cube = [CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]];
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23);
+    let gray_value = (8 + gray_step * 10) as u8;
+    let gray_index = 232 + gray_step as usize;
+
+    if distance([r, g, b], [gray_value; 3]) < distance([r, g, b], cube) {
+        gray_index as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+/// Closest of the 16 basic terminal colors
+pub fn nearest_16(r: u8, g: u8, b: u8) -> u8 {
+    BASIC_COLORS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, color)| distance([r, g, b], **color))
+        .map_or(0, |(index, _)| index as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_colors_pick_the_nearest_palette_entry() {
+        assert_eq!(nearest_16(0, 0, 0), 0);
+        assert_eq!(nearest_16(190, 20, 10), 1);
+        assert_eq!(nearest_16(250, 10, 10), 9);
+        assert_eq!(nearest_16(120, 130, 125), 8);
+        assert_eq!(nearest_16(240, 240, 250), 15);
+        for (index, color) in BASIC_COLORS.iter().enumerate() {
+            assert_eq!(nearest_16(color[0], color[1], color[2]), index as u8);
+        }
+    }
+
+    #[test]
+    fn grays_use_the_gray_ramp_unless_the_cube_is_closer() {
+        // Halfway between cube levels 95 and 135, right on a ramp step
+        assert_eq!(nearest_256(128, 128, 128), 244);
+        assert_eq!(TermColor::Indexed(244).rgb(), [128; 3]);
+        // Exactly a gray of the cube
+        assert_eq!(nearest_256(95, 95, 95), 59);
+        assert_eq!(nearest_256(0, 0, 0), 16);
+        assert_eq!(nearest_256(255, 255, 255), 231);
+        // Colors only fit the cube
+        assert_eq!(nearest_256(255, 0, 0), 196);
+        assert_eq!(nearest_256(100, 140, 210), 16 + 36 + 2 * 6 + 4);
+    }
+
+    #[test]
+    fn palette_colors_quantize_to_themselves() {
+        for index in 16..=255 {
+            let [r, g, b] = TermColor::Indexed(index).rgb();
+            assert_eq!(nearest_256(r, g, b), index, "{:?}", [r, g, b]);
+        }
+    }
+
+    #[test]
+    fn modes_pick_their_kind_of_color() {
+        let pixel = Rgb([250, 10, 10]);
+        assert_eq!(
+            TermColor::from_pixel(&pixel, ColorMode::Truecolor),
+            Some(TermColor::Rgb(250, 10, 10))
+        );
+        assert_eq!(
+            TermColor::from_pixel(&pixel, ColorMode::Ansi256),
+            Some(TermColor::Indexed(196))
+        );
+        assert_eq!(
+            TermColor::from_pixel(&pixel, ColorMode::Ansi16),
+            Some(TermColor::Basic(9))
+        );
+        assert_eq!(TermColor::from_pixel(&pixel, ColorMode::None), None);
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::time::Duration;
+
+/// Playback command triggered from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    TogglePause,
+    /// Relative seek in seconds
+    Seek(f64),
+    /// Relative playback speed change
+    Speed(f64),
+    StepForward,
+    StepBack,
+    /// The terminal now has this many columns and rows
+    Resize(u16, u16),
+}
+
+/// Seconds skipped by the left/right arrow keys
+pub const SEEK_STEP: f64 = 5.0;
+/// Speed change applied by `+`/`-`
+pub const SPEED_STEP: f64 = 0.25;
+pub const MIN_SPEED: f64 = 0.25;
+pub const MAX_SPEED: f64 = 4.0;
+
+fn action_for(code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+    match code {
+        // Raw mode swallows SIGINT, so Ctrl+C has to be handled as a key
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Some(Action::Quit),
+        KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+        KeyCode::Char(' ') => Some(Action::TogglePause),
+        KeyCode::Left => Some(Action::Seek(-SEEK_STEP)),
+        KeyCode::Right => Some(Action::Seek(SEEK_STEP)),
+        KeyCode::Char('+') | KeyCode::Char('=') => Some(Action::Speed(SPEED_STEP)),
+        KeyCode::Char('-') => Some(Action::Speed(-SPEED_STEP)),
+        KeyCode::Char('.') => Some(Action::StepForward),
+        KeyCode::Char(',') => Some(Action::StepBack),
+        _ => None,
+    }
+}
+
+/// Waits up to `timeout` for a key press or resize and maps it to an action
+///
+/// Returns `None` on timeout and for events that don't map to an action, so callers should
+/// re-check their own deadline rather than assume the full timeout has passed.
+pub fn poll_action(timeout: Duration) -> Result<Option<Action>> {
+    if !event::poll(timeout)? {
+        return Ok(None);
+    }
+
+    match event::read()? {
+        Event::Key(key) if key.kind != KeyEventKind::Release => {
+            Ok(action_for(key.code, key.modifiers))
+        }
+        Event::Resize(columns, rows) => Ok(Some(Action::Resize(columns, rows))),
+        _ => Ok(None),
+    }
+}
@@ -1,235 +1,582 @@
-use crate::types::{cleanup_guard::CleanupGuard, consts::EAGAIN, convert_args::ConvertArgs};
-use anyhow::{Context, Result, anyhow};
-use ffmpeg::software::scaling;
-use ffmpeg_next as ffmpeg;
-use image::{ImageBuffer, Rgb};
-use std::{
-    convert::TryInto,
-    fs,
-    io::Write,
-    path::{Path, PathBuf},
+use crate::error::{Error, Warnings};
+use crate::layout::Layout;
+use crate::manifest::Manifest;
+use crate::render::{Renderer, renderer_for};
+use crate::sink::{self, FrameSink, SinkHeader};
+use crate::source::{FrameSource, Input, Picture, STDIN_PATH, Timeline, scale::FrameScaler};
+use crate::tone;
+use crate::transcode::transcode_audio;
+use crate::types::{
+    audio_format::AudioFormat,
+    consts::{AUDIO_FILE_STEM, DEFAULT_CONVERT_FPS},
+    frame_job::FrameJob,
+    output_format::OutputFormat,
+    render_options::RenderOptions,
+    tone_options::ToneOptions,
 };
-use sysx::utils::{
-    ascii::{AsciiArtConfig, CHAR_SET_VERY_DETAILED, image_to_ascii_configurable},
-    term::txy,
+use anyhow::{Result, anyhow};
+use std::{
+    collections::BTreeSet,
+    mem,
+    path::PathBuf,
+    sync::{
+        Condvar, Mutex,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver},
+    },
+    thread,
 };
 
-pub fn run_conversion(args: ConvertArgs) -> Result<()> {
-    ffmpeg::init().context("Failed to initialize FFmpeg")?;
-
-    let input_path = Path::new(&args.input);
-    if !input_path.exists() {
-        return Err(anyhow!("Input file not found: {}", args.input));
-    }
-
-    let main_output_dir_path = Path::new(&args.output_dir);
-    fs::create_dir_all(main_output_dir_path).with_context(|| {
-        format!("Failed to create main output directory: {main_output_dir_path:?}")
-    })?;
-
-    let temp_frame_path = main_output_dir_path.join("_temp_frame.png");
-    let _cleanup_guard = CleanupGuard::new(temp_frame_path.clone()); // Use the guard for cleanup
-
-    let mut ictx = ffmpeg::format::input(&input_path)
-        .with_context(|| format!("Failed to open input file: {}", args.input))?;
-
-    let input_stream = ictx
-        .streams()
-        .best(ffmpeg::media::Type::Video)
-        .ok_or_else(|| anyhow!("Could not find video stream in input file"))?;
-    let video_stream_index = input_stream.index();
-    let codec_parameters = input_stream.parameters();
-
-    let mut decoder = ffmpeg::codec::context::Context::from_parameters(codec_parameters)?
-        .decoder()
-        .video()?;
-    let frame_time_base = input_stream.time_base();
-    let video_fps: f64 = input_stream.rate().into();
-    let target_fps = args.fps;
-    let min_pts_difference = (video_fps / target_fps).round() as i64;
-
-    let mut scaler = scaling::Context::get(
-        decoder.format(),
-        decoder.width(),
-        decoder.height(),
-        ffmpeg::format::Pixel::RGB24,
-        decoder.width(),
-        decoder.height(),
-        scaling::Flags::BILINEAR,
-    )?;
-    let mut video_frame_count = 0;
-    let mut total_output_frames = 0;
-    let mut last_processed_time_pts = -1;
-    let mut last_processed_second: Option<u64> = None;
-    let mut current_second_dir: Option<PathBuf> = None;
-    let mut frame_count_in_second = 0;
-
-    let (mut ascii_width, mut ascii_height) = (args.width, args.height);
-
-    if args.auto_size {
-        if let Some((term_width, term_height)) = txy() {
-            if args.width == 100 {
-                // only if width is not specified (default value)
-                ascii_width = term_width as usize;
-            }
-            if args.height == 50 {
-                // only if height is not specified (default value)
-                ascii_height = term_height as usize;
+/// Called with the number of frames written so far, from the conversion worker threads
+type Progress = dyn Fn(u64) + Send + Sync;
+
+/// Slots handed to the workers that are not written yet, shared with the decoder
+///
+/// Workers finish frames out of order and ordered sinks hold back the early ones, so the
+/// decoder may only run `depth` slots ahead of the oldest frame still being worked on.
+/// That bounds what those sinks keep in memory however slow a single frame is.
+struct Window {
+    depth: u64,
+    state: Mutex<WindowState>,
+    advanced: Condvar,
+}
+
+struct WindowState {
+    /// Every slot before this one has been written
+    next: u64,
+    /// Slots written after `next`
+    written: BTreeSet<u64>,
+    /// Set once a worker failed, to stop the decoder
+    closed: bool,
+}
+
+impl Window {
+    fn new(depth: usize) -> Self {
+        Self {
+            depth: depth as u64,
+            state: Mutex::new(WindowState {
+                next: 0,
+                written: BTreeSet::new(),
+                closed: false,
+            }),
+            advanced: Condvar::new(),
+        }
+    }
+
+    /// Waits until `slot` may be handed out, returning `false` if the conversion failed
+    fn admit(&self, slot: u64) -> bool {
+        let Ok(state) = self.state.lock() else {
+            return false;
+        };
+        self.advanced
+            .wait_while(state, |state| {
+                !state.closed && slot >= state.next + self.depth
+            })
+            .is_ok_and(|state| !state.closed)
+    }
+
+    fn written(&self, slot: u64) {
+        if let Ok(mut state) = self.state.lock() {
+            let state = &mut *state;
+            state.written.insert(slot);
+            while state.written.remove(&state.next) {
+                state.next += 1;
             }
+            self.advanced.notify_all();
+        }
+    }
+
+    fn close(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+            self.advanced.notify_all();
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state.lock().map_or(true, |state| state.closed)
+    }
+}
+
+/// Closes the window when a worker panics, so the decoder doesn't wait for its frame forever
+struct CloseOnPanic<'a>(&'a Window);
+
+impl Drop for CloseOnPanic<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.close();
+        }
+    }
+}
+
+/// What the conversion workers share
+struct Workers<'a> {
+    queue: Mutex<Receiver<FrameJob>>,
+    window: Window,
+    layout: Layout,
+    tone: ToneOptions,
+    renderer: &'a dyn Renderer,
+    sink: &'a dyn FrameSink,
+    written_frames: AtomicU64,
+    progress: Option<&'a Progress>,
+}
+
+impl Workers<'_> {
+    /// Scales, tones, lays out and renders an image; text is stored as it is
+    ///
+    /// Tone is adjusted before the image is placed, so letterbox bars stay black and don't
+    /// count towards automatic levels.
+    fn render(&self, picture: Picture, scaler: &mut FrameScaler) -> Result<String> {
+        let image = match picture {
+            Picture::Text(text) => return Ok(text),
+            Picture::Image(image) => image,
+            Picture::Unscaled(frame) => frame.scale(scaler)?,
+        };
+        let image = tone::adjust(&image, &self.tone);
+        Ok(self.renderer.render(&self.layout.place(&image)))
+    }
+}
+
+/// Worker loop: draws queued frames and writes them until the queue closes
+///
+/// Every worker scales frames with its own scaler, so only decoding is left to the decoder
+/// thread. A frame that can't be drawn or stored fails the whole conversion, as every later
+/// frame would be held back behind it.
+fn process_jobs(workers: &Workers) -> crate::Result<()> {
+    let window = &workers.window;
+    let _close_on_panic = CloseOnPanic(window);
+    let mut scaler = FrameScaler::default();
+    loop {
+        let job = match workers.queue.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return Ok(()),
+        };
+        let Ok(job) = job else {
+            return Ok(());
+        };
+        if window.is_closed() {
+            return Ok(());
+        }
+
+        let frame = job.video_frame;
+        let stored = workers
+            .render(job.picture, &mut scaler)
+            .map_err(|e| Error::input(e.context(format!("Failed to draw frame {frame}"))))
+            .and_then(|ascii_art| {
+                workers
+                    .sink
+                    .write(job.slot, &ascii_art)
+                    .map_err(|e| Error::output(e.context(format!("Failed to store frame {frame}"))))
+            });
+        if let Err(e) = stored {
+            window.close();
+            return Err(e);
+        }
+        window.written(job.slot);
+
+        let total_output_frames = workers.written_frames.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(progress) = workers.progress {
+            progress(total_output_frames);
+        }
+    }
+}
+
+/// Converts a video, animated image, image sequence or other frame source to ASCII frames
+/// stored on disk or streamed
+///
+/// ```no_run
+/// use ascii4::{Converter, OutputFormat};
+///
+/// let manifest = Converter::new("clip.mp4", "clip.a4p")
+///     .fps(24.0)
+///     .format(OutputFormat::Pack)
+///     .compress(true)
+///     .run()?;
+/// println!("{manifest}");
+/// # Ok::<(), ascii4::Error>(())
+/// ```
+pub struct Converter {
+    input: Input,
+    output: PathBuf,
+    fps: f64,
+    jobs: Option<usize>,
+    render: RenderOptions,
+    format: OutputFormat,
+    compress: bool,
+    audio: Option<AudioFormat>,
+    sink: Option<Box<dyn FrameSink>>,
+    progress: Option<Box<Progress>>,
+    warnings: Warnings,
+}
+
+impl Converter {
+    /// Converts `input` into frames stored at `output`, a directory or a file depending on
+    /// [`Converter::format`]
+    ///
+    /// The input is opened with [`open_source`](crate::open_source), so it can be anything
+    /// from a video or `-` for standard input to frames converted earlier.
+    pub fn new(input: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Self {
+        Self::with_input(Input::Path(input.into()), output.into())
+    }
+
+    /// Converts frames from a source opened by the caller
+    pub fn from_source(source: Box<dyn FrameSource>, output: impl Into<PathBuf>) -> Self {
+        Self::with_input(Input::Source(source), output.into())
+    }
+
+    fn with_input(input: Input, output: PathBuf) -> Self {
+        Self {
+            input,
+            output,
+            fps: DEFAULT_CONVERT_FPS,
+            jobs: None,
+            render: RenderOptions::default(),
+            format: OutputFormat::default(),
+            compress: false,
+            audio: None,
+            sink: None,
+            progress: None,
+            warnings: Warnings::default(),
+        }
+    }
+
+    /// Frames per second of the output timeline
+    pub fn fps(mut self, fps: f64) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// Number of rendering threads; defaults to the available cores
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Sizing and glyph options
+    pub fn render(mut self, render: RenderOptions) -> Self {
+        self.render = render;
+        self
+    }
+
+    /// Layout the frames are written in; ignored when a [`Converter::sink`] is set
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Compresses frames with zstd; only packs support it
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Stores the soundtrack next to the frames in `format`
+    pub fn audio(mut self, format: AudioFormat) -> Self {
+        self.audio = Some(format);
+        self
+    }
+
+    /// Writes frames to a sink of the caller's instead of the one [`Converter::format`] picks
+    ///
+    /// The manifest and soundtrack are stored with the sink's
+    /// [`location`](FrameSink::location), and skipped if it has none.
+    pub fn sink(mut self, sink: Box<dyn FrameSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Reports the number of frames written so far after every frame, from worker threads
+    pub fn progress(mut self, progress: impl Fn(u64) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Reports problems that don't stop the conversion, such as a skipped frame file or an
+    /// audio track that can't be stored
+    pub fn warnings(mut self, handler: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.warnings = Warnings::new(handler);
+        self
+    }
+
+    /// Runs the conversion, returning the manifest stored with the frames
+    pub fn run(mut self) -> crate::Result<Manifest> {
+        if self.fps <= 0.0 {
+            return Err(Error::InvalidOption("FPS must be positive".to_string()));
+        }
+        self.render.tone.validate()?;
+
+        let name = self.input.name();
+        let input = mem::replace(&mut self.input, Input::Path(PathBuf::new()));
+        let mut source = input
+            .open(Some(self.fps), &self.warnings)
+            .map_err(Error::input)?;
+
+        let charset = self.render.ramp();
+        let renderer = renderer_for(&self.render);
+        let layout = self.render.layout(
+            source.size(),
+            renderer.cell_size(),
+            self.render.auto_size,
+            &self.warnings,
+        )?;
+        // Scale straight to the size the layout expects, so no resampling happens later
+        source.scale_to(layout.scaled).map_err(Error::input)?;
+
+        // Frames rendered earlier are copied as they are
+        let (ramp, (ascii_width, ascii_height)) = if source.is_text() {
+            let ramp = source
+                .charset()
+                .unwrap_or_else(|| charset.ramp().iter().collect());
+            (ramp, source.size())
         } else {
-            eprintln!("Warning: Could not determine terminal size, using default dimensions");
+            (charset.ramp().iter().collect(), layout.cells)
+        };
+
+        let mut sink = self
+            .sink
+            .take()
+            .unwrap_or_else(|| sink::open_sink(self.format, &self.output, self.compress));
+        sink.start(&SinkHeader {
+            fps: self.fps,
+            width: ascii_width,
+            height: ascii_height,
+            charset: ramp.clone(),
+            color: self.render.color,
+        })
+        .map_err(Error::output)?;
+
+        let jobs = self
+            .jobs
+            .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .max(1);
+        // The window keeps at most `depth` frames in flight, so sends never block for long
+        let depth = jobs * 2;
+        let (job_tx, job_rx) = mpsc::sync_channel::<FrameJob>(depth);
+        let workers = Workers {
+            queue: Mutex::new(job_rx),
+            window: Window::new(depth),
+            layout,
+            tone: self.render.tone,
+            renderer: renderer.as_ref(),
+            sink: sink.as_ref(),
+            written_frames: AtomicU64::new(0),
+            progress: self.progress.as_deref(),
+        };
+
+        let (decoded, stored) = thread::scope(|scope| {
+            let handles: Vec<_> = (0..jobs)
+                .map(|_| scope.spawn(|| process_jobs(&workers)))
+                .collect();
+
+            // Owned by the decoder so that returning closes the queue and releases the workers
+            let decoded = {
+                let job_tx = job_tx;
+                let emit = |slot, picture: &Picture, video_frame| {
+                    if !workers.window.admit(slot) {
+                        return Err(anyhow!("Conversion stopped after a failed frame"));
+                    }
+                    job_tx
+                        .send(FrameJob {
+                            picture: picture.clone(),
+                            slot,
+                            video_frame,
+                        })
+                        .map_err(|_| anyhow!("All conversion workers have stopped"))
+                };
+                Timeline::new(self.fps).run(source.as_mut(), emit)
+            };
+
+            let stored = handles.into_iter().try_for_each(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(Error::output(anyhow!("A conversion worker panicked"))))
+            });
+            (decoded, stored)
+        });
+        // A failed frame is what stopped the decoder, so it is the error worth reporting
+        stored?;
+        decoded.map_err(Error::input)?;
+        let frame_count = workers.written_frames.load(Ordering::Relaxed);
+
+        sink.finish().map_err(Error::output)?;
+
+        let mut manifest = Manifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            source: name,
+            duration: source.duration(),
+            fps: self.fps,
+            width: ascii_width,
+            height: ascii_height,
+            charset: ramp,
+            color: self.render.color,
+            frame_count,
+            audio: None,
+        };
+
+        let Some(location) = sink.location() else {
+            if self.audio.is_some() {
+                self.warnings
+                    .warn("Output has nowhere to keep the audio track, skipping it");
+            }
+            return Ok(manifest);
+        };
+        if let Some(audio_format) = self.audio {
+            let audio_path = if location.is_dir() {
+                location.join(format!("{AUDIO_FILE_STEM}.{}", audio_format.extension()))
+            } else {
+                location.with_extension(audio_format.extension())
+            };
+
+            let extracted = match source.audio_path() {
+                Some(audio_input) => transcode_audio(&audio_input, &audio_path, audio_format)
+                    .map_err(Error::audio)?,
+                None => false,
+            };
+            if extracted {
+                manifest.audio = audio_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned());
+            } else if manifest.source == STDIN_PATH {
+                // The stream has been read by the time frames are done, so there's no going back
+                self.warnings
+                    .warn("Audio can't be extracted from standard input, skipping it");
+            } else {
+                self.warnings
+                    .warn("Input has no audio track, nothing to extract");
+            }
         }
+
+        manifest.save(location).map_err(Error::output)?;
+        Ok(manifest)
     }
 
-    let ascii_width: u32 = ascii_width.try_into().context("Width value too large")?;
-    let ascii_height: u32 = ascii_height.try_into().context("Height value too large")?;
+    /// Directory or file the frames end up in; files get the format's extension if the output
+    /// has none
+    pub fn output_path(&self) -> PathBuf {
+        sink::output_path(self.format, &self.output)
+    }
+}
 
-    let ascii_config = AsciiArtConfig {
-        width: ascii_width,
-        height: ascii_height,
-        char_set: CHAR_SET_VERY_DETAILED.chars().collect(),
-        ..Default::default()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::SourceFrame;
+    use std::{
+        sync::{Arc, atomic::AtomicBool},
+        time::Duration,
     };
 
-    for (stream, packet) in ictx.packets() {
-        if stream.index() == video_stream_index {
-            match decoder.send_packet(&packet) {
-                Ok(()) => (),
-                Err(e) if matches!(e, ffmpeg::Error::Other { .. }) => {
-                    eprintln!("\nWarning: Non-fatal error when sending packet: {e}");
-                }
-                Err(e) => {
-                    return Err(anyhow!("Failed to send packet to decoder: {}", e));
-                }
-            }
+    /// Text frames one second apart, endless when `count` is `None`
+    struct Counting {
+        count: Option<u64>,
+        next: u64,
+    }
 
-            let mut decoded_frame = ffmpeg::frame::Video::empty();
-            loop {
-                match decoder.receive_frame(&mut decoded_frame) {
-                    Ok(()) => {
-                        video_frame_count += 1;
-                        let current_pts = decoded_frame.pts().unwrap_or(0);
-
-                        if current_pts >= 0
-                            && (last_processed_time_pts == -1
-                                || (current_pts - last_processed_time_pts) >= min_pts_difference)
-                        {
-                            last_processed_time_pts = current_pts;
-
-                            let current_second = (current_pts as f64
-                                * frame_time_base.numerator() as f64
-                                / frame_time_base.denominator() as f64)
-                                .floor() as u64;
-
-                            if last_processed_second != Some(current_second) {
-                                let second_dir =
-                                    main_output_dir_path.join(current_second.to_string());
-                                fs::create_dir_all(&second_dir).with_context(|| {
-                                    format!("Failed to create directory for second {current_second}: {second_dir:?}")
-                                })?;
-                                current_second_dir = Some(second_dir);
-                                frame_count_in_second = 1;
-                                last_processed_second = Some(current_second);
-                            } else {
-                                frame_count_in_second += 1;
-                            }
-
-                            let output_dir = match &current_second_dir {
-                                Some(dir) => dir,
-                                None => {
-                                    eprintln!(
-                                        "Error: Current second directory not set for frame {video_frame_count}. Skipping."
-                                    );
-                                    continue;
-                                }
-                            };
-
-                            let mut rgb_frame = ffmpeg::frame::Video::empty();
-                            if scaler.run(&decoded_frame, &mut rgb_frame).is_err() {
-                                eprintln!(
-                                    "Warning: Scaling failed for frame {video_frame_count}. Skipping."
-                                );
-                                continue;
-                            }
-
-                            let img_buf: ImageBuffer<Rgb<u8>, Vec<u8>> = match ImageBuffer::from_raw(
-                                rgb_frame.width(),
-                                rgb_frame.height(),
-                                rgb_frame.data(0).to_vec(),
-                            ) {
-                                Some(buf) => buf,
-                                None => {
-                                    eprintln!(
-                                        "Warning: Failed to create image buffer for frame {video_frame_count}. Skipping."
-                                    );
-                                    continue;
-                                }
-                            };
-
-                            if img_buf.save(&temp_frame_path).is_err() {
-                                eprintln!(
-                                    "Warning: Failed to save temporary frame {video_frame_count}. Skipping."
-                                );
-                                continue;
-                            }
-
-                            match image_to_ascii_configurable(&temp_frame_path, &ascii_config) {
-                                Ok(ascii_art) => {
-                                    total_output_frames += 1;
-                                    let output_filename =
-                                        output_dir.join(format!("{frame_count_in_second}.txt"));
-                                    match fs::File::create(&output_filename) {
-                                        Ok(mut file) => {
-                                            if file.write_all(ascii_art.as_bytes()).is_err() {
-                                                eprintln!(
-                                                    "\nWarning: Failed to write ASCII art to file: {output_filename:?}"
-                                                );
-                                            }
-                                        }
-                                        Err(e) => {
-                                            eprintln!(
-                                                "\nWarning: Failed to create output file {output_filename:?}: {e}"
-                                            );
-                                        }
-                                    }
-
-                                    if total_output_frames % 10 == 0 {
-                                        print!("\rProcessed ASCII frames: {total_output_frames}");
-                                        std::io::stdout().flush().unwrap_or_default();
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!(
-                                        "\nWarning: Failed to convert frame {video_frame_count} (sec {current_second}, frame {frame_count_in_second}) to ASCII: {e}"
-                                    );
-                                }
-                            }
-                        }
-                    }
-                    Err(ffmpeg::Error::Eof) => {
-                        break;
-                    }
-                    Err(ffmpeg::Error::Other { errno }) if errno == EAGAIN => {
-                        break;
-                    }
-                    Err(e) => {
-                        eprintln!("\nWarning: Error receiving frame: {e}");
-                        break;
-                    }
-                }
+    impl FrameSource for Counting {
+        fn size(&self) -> (u32, u32) {
+            (4, 1)
+        }
+
+        fn duration(&self) -> Option<f64> {
+            self.count.map(|count| count as f64)
+        }
+
+        fn fps(&self) -> Option<f64> {
+            Some(1.0)
+        }
+
+        fn is_text(&self) -> bool {
+            true
+        }
+
+        fn scale_to(&mut self, _size: (u32, u32)) -> Result<()> {
+            Ok(())
+        }
+
+        fn next_frame(&mut self, time: f64) -> Result<Option<SourceFrame>> {
+            self.next = self.next.max(time.floor() as u64);
+            if self.count.is_some_and(|count| self.next >= count) {
+                return Ok(None);
             }
+            let frame = SourceFrame {
+                time: self.next as f64,
+                end: (self.next + 1) as f64,
+                number: self.next + 1,
+                picture: Picture::Text(format!("{:4}", self.next)),
+            };
+            self.next += 1;
+            Ok(Some(frame))
+        }
+
+        fn seek(&mut self, time: f64) -> Result<()> {
+            self.next = time.floor() as u64;
+            Ok(())
         }
     }
 
-    if let Err(e) = decoder.send_eof() {
-        if e != ffmpeg::Error::Eof {
-            eprintln!("\nWarning: Failed to send final EOF to decoder: {e}");
+    /// Sink that is slow to store slot 0 and fails at `fail_at`
+    #[derive(Clone, Default)]
+    struct Recording {
+        fail_at: Option<u64>,
+        first_written: Arc<AtomicBool>,
+        /// Highest slot written while slot 0 was still pending
+        ahead: Arc<AtomicU64>,
+        highest: Arc<AtomicU64>,
+    }
+
+    impl FrameSink for Recording {
+        fn start(&mut self, _header: &SinkHeader) -> Result<()> {
+            Ok(())
+        }
+
+        fn write(&self, slot: u64, _content: &str) -> Result<()> {
+            self.highest.fetch_max(slot, Ordering::Relaxed);
+            if self.fail_at == Some(slot) {
+                return Err(anyhow!("Broken pipe"));
+            }
+            if slot == 0 {
+                thread::sleep(Duration::from_millis(100));
+                self.first_written.store(true, Ordering::Relaxed);
+            } else if !self.first_written.load(Ordering::Relaxed) {
+                self.ahead.fetch_max(slot, Ordering::Relaxed);
+            }
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            Ok(())
         }
     }
 
-    let mut decoded_frame = ffmpeg::frame::Video::empty();
-    while decoder.receive_frame(&mut decoded_frame).is_ok() {
-        video_frame_count += 1;
+    fn convert(count: Option<u64>, sink: &Recording) -> crate::Result<Manifest> {
+        let source = Counting { count, next: 0 };
+        Converter::from_source(Box::new(source), "unused")
+            .fps(1.0)
+            .jobs(2)
+            .sink(Box::new(sink.clone()))
+            .run()
+    }
+
+    #[test]
+    fn failed_writes_stop_the_conversion() {
+        let sink = Recording {
+            fail_at: Some(5),
+            ..Recording::default()
+        };
+
+        let result = convert(None, &sink);
+        assert!(matches!(result, Err(Error::Output(_))), "{result:?}");
+        // Two workers keep at most four frames in flight
+        assert!(sink.highest.load(Ordering::Relaxed) < 5 + 4);
+    }
+
+    #[test]
+    fn slow_frames_hold_back_at_most_the_window() {
+        let sink = Recording::default();
+
+        let manifest = convert(Some(40), &sink).unwrap();
+        assert_eq!(manifest.frame_count, 40);
+        assert!(sink.ahead.load(Ordering::Relaxed) < 4);
     }
-    Ok(())
 }
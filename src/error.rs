@@ -0,0 +1,81 @@
+use std::{error::Error as StdError, fmt, sync::Arc};
+
+/// Underlying cause of an [`Error`], with its own chain of sources
+pub type Cause = Box<dyn StdError + Send + Sync + 'static>;
+
+/// Result of the public conversion and playback API
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// What went wrong in a conversion or playback, by the stage that failed
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// An option is out of range or conflicts with another one
+    #[error("{0}")]
+    InvalidOption(String),
+
+    /// The input video, image or frames could not be opened or decoded
+    #[error("Failed to read input")]
+    Input(#[source] Cause),
+
+    /// Frames, the manifest or the soundtrack could not be written
+    #[error("Failed to write output")]
+    Output(#[source] Cause),
+
+    /// The soundtrack could not be extracted or played
+    #[error("Audio failed")]
+    Audio(#[source] Cause),
+
+    /// Drawing to or reading keys from the terminal failed
+    #[error("Terminal I/O failed")]
+    Terminal(#[source] Cause),
+}
+
+impl Error {
+    pub(crate) fn input(cause: impl Into<anyhow::Error>) -> Self {
+        Self::Input(cause.into().into())
+    }
+
+    pub(crate) fn output(cause: impl Into<anyhow::Error>) -> Self {
+        Self::Output(cause.into().into())
+    }
+
+    pub(crate) fn audio(cause: impl Into<anyhow::Error>) -> Self {
+        Self::Audio(cause.into().into())
+    }
+
+    pub(crate) fn terminal(cause: impl Into<anyhow::Error>) -> Self {
+        Self::Terminal(cause.into().into())
+    }
+}
+
+/// Receives problems that don't stop a conversion or playback, such as a skipped file or a
+/// corrupt packet
+///
+/// Warnings are dropped unless a handler is given. Clones share the handler.
+#[derive(Clone, Default)]
+pub struct Warnings(Option<Arc<WarningHandler>>);
+
+/// Callback warnings are passed to, as a message without a trailing newline
+type WarningHandler = dyn Fn(&str) + Send + Sync;
+
+impl Warnings {
+    /// Passes every warning to `handler`, from whichever thread it comes up on
+    pub fn new(handler: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(handler)))
+    }
+
+    pub(crate) fn warn(&self, message: impl fmt::Display) {
+        if let Some(handler) = &self.0 {
+            handler(&message.to_string());
+        }
+    }
+}
+
+impl fmt::Debug for Warnings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Warnings")
+            .field(&self.0.as_ref().map(|_| "handler"))
+            .finish()
+    }
+}
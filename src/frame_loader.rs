@@ -0,0 +1,241 @@
+use crate::layout::Layout;
+use crate::render::Renderer;
+use crate::source::{FrameSource, Picture, Timeline, scale::FrameScaler, slot_count};
+use crate::tone;
+use crate::types::tone_options::ToneOptions;
+use anyhow::{Result, anyhow};
+use image::RgbImage;
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+/// Rendered frame together with its position in the animation
+#[derive(Clone, Debug)]
+pub struct Frame {
+    /// Position from the start of the animation
+    pub index: usize,
+    /// Text of the frame, including any color escape sequences
+    pub content: String,
+}
+
+/// Message from the loader thread; `frame` is `None` once the end of the animation is reached
+struct Loaded {
+    generation: u64,
+    frame: Option<Result<Frame>>,
+}
+
+/// Request to continue loading from another frame, optionally laid out anew
+struct SeekRequest {
+    generation: u64,
+    index: usize,
+    layout: Option<Layout>,
+}
+
+/// Reads frames on a background thread, keeping at most a fixed number of them in memory
+///
+/// Every seek starts a new generation; frames of older generations still queued in the
+/// channel are discarded by the receiving side. The loader stops on its own once the
+/// receiving side is dropped.
+pub struct FrameLoader {
+    frames: Receiver<Loaded>,
+    seeks: Sender<SeekRequest>,
+    generation: u64,
+    len: usize,
+    seekable: bool,
+}
+
+impl FrameLoader {
+    /// Starts reading `source` from the first frame at `fps`, wrapping around forever when
+    /// `looping` and the source can seek
+    ///
+    /// Image frames are adjusted by `tone` and drawn by `renderer`, arranged according to
+    /// `layout`. The length is
+    /// estimated from the source duration, so the last frame may be numbered slightly past it
+    /// or the animation may end a little early.
+    pub fn spawn(
+        mut source: Box<dyn FrameSource>,
+        renderer: Box<dyn Renderer>,
+        mut layout: Layout,
+        tone: ToneOptions,
+        fps: f64,
+        read_ahead: usize,
+        looping: bool,
+    ) -> Self {
+        let len = source
+            .duration()
+            .map_or(0, |duration| slot_count(duration, fps) as usize);
+        let seekable = source.can_seek();
+        let looping = looping && seekable;
+        let (frame_tx, frame_rx) = mpsc::sync_channel(read_ahead.max(1));
+        let (seek_tx, seek_rx) = mpsc::channel::<SeekRequest>();
+
+        thread::spawn(move || {
+            if let Err(e) = source.scale_to(layout.scaled) {
+                let _ = frame_tx.send(Loaded {
+                    generation: 0,
+                    frame: Some(Err(e)),
+                });
+                return;
+            }
+
+            let mut generation = 0;
+            let mut timeline = Timeline::new(fps);
+            let mut scaler = FrameScaler::default();
+            // Past the end or after an error, nothing more can be read until the next seek
+            let mut exhausted = false;
+            loop {
+                let seek = if exhausted {
+                    match seek_rx.recv() {
+                        Ok(seek) => Some(seek),
+                        Err(_) => return,
+                    }
+                } else {
+                    seek_rx.try_iter().last()
+                };
+                if let Some(seek) = seek {
+                    generation = seek.generation;
+                    exhausted = false;
+                    let relaid = match seek.layout {
+                        Some(new_layout) => source.scale_to(new_layout.scaled).map(|()| {
+                            layout = new_layout;
+                        }),
+                        None => Ok(()),
+                    };
+                    // Sources that can't seek are only laid out anew and go on where they are
+                    let moved = relaid.and_then(|()| {
+                        if seekable {
+                            source.seek(seek.index as f64 / fps)
+                        } else {
+                            Ok(())
+                        }
+                    });
+                    if let Err(e) = moved {
+                        exhausted = true;
+                        if frame_tx
+                            .send(Loaded {
+                                generation,
+                                frame: Some(Err(e)),
+                            })
+                            .is_err()
+                        {
+                            return;
+                        }
+                        continue;
+                    }
+                    if seekable {
+                        timeline.restart_at(seek.index as u64);
+                    }
+                }
+
+                let mut stopped = false;
+                let advanced = timeline.advance(source.as_mut(), |slot, picture, _| {
+                    let draw = |image: &RgbImage| {
+                        renderer.render(&layout.place(&tone::adjust(image, &tone)))
+                    };
+                    let content = match picture {
+                        Picture::Text(text) => text.clone(),
+                        Picture::Image(image) => draw(image),
+                        Picture::Unscaled(frame) => draw(&frame.scale(&mut scaler)?),
+                    };
+                    let sent = frame_tx.send(Loaded {
+                        generation,
+                        frame: Some(Ok(Frame {
+                            index: slot as usize,
+                            content,
+                        })),
+                    });
+                    stopped = sent.is_err();
+                    sent.map_err(|_| anyhow!("Player has stopped"))
+                });
+                if stopped {
+                    return;
+                }
+
+                let end = match advanced {
+                    Ok(true) => continue,
+                    Ok(false) if looping => match source.seek(0.0) {
+                        Ok(()) => {
+                            timeline.restart_at(0);
+                            continue;
+                        }
+                        Err(e) => Some(Err(e)),
+                    },
+                    Ok(false) => None,
+                    Err(e) => Some(Err(e)),
+                };
+
+                exhausted = true;
+                if frame_tx
+                    .send(Loaded {
+                        generation,
+                        frame: end,
+                    })
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            frames: frame_rx,
+            seeks: seek_tx,
+            generation: 0,
+            len,
+            seekable,
+        }
+    }
+
+    /// Total number of frames in one pass of the animation, 0 if the length is unknown
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the source can seek; without it seeks are ignored and nothing loops
+    pub fn can_seek(&self) -> bool {
+        self.seekable
+    }
+
+    /// Blocks until the next frame is loaded; `None` once the animation has ended
+    pub fn next_frame(&self) -> Option<Result<Frame>> {
+        loop {
+            let loaded = self.frames.recv().ok()?;
+            if loaded.generation == self.generation {
+                return loaded.frame;
+            }
+        }
+    }
+
+    /// Makes the next frame returned by [`FrameLoader::next_frame`] the one at `index`
+    ///
+    /// Ignored when the source can't seek.
+    pub fn seek(&mut self, index: usize) {
+        if self.seekable {
+            self.request(index, None);
+        }
+    }
+
+    /// Like [`FrameLoader::seek`], rendering frames from `index` on with `layout`
+    ///
+    /// Text frames rendered earlier are not affected. A source that can't seek keeps going
+    /// from where it is, and frames already loaded are still shown at their old size.
+    pub fn relayout(&mut self, layout: Layout, index: usize) {
+        self.request(index, Some(layout));
+    }
+
+    fn request(&mut self, index: usize, layout: Option<Layout>) {
+        if self.seekable {
+            self.generation += 1;
+        }
+        let index = match self.len {
+            0 => index,
+            len => index.min(len - 1),
+        };
+        let _ = self.seeks.send(SeekRequest {
+            generation: self.generation,
+            index,
+            layout,
+        });
+    }
+}
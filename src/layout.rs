@@ -0,0 +1,177 @@
+use crate::types::fit_mode::FitMode;
+use image::{RgbImage, imageops};
+use std::borrow::Cow;
+
+/// Where a source image lands on the pixel grid a renderer draws from
+///
+/// The source is scaled to `scaled` pixels and its top-left corner placed at `offset` on a
+/// black canvas of `cells` times the renderer's cell size: a positive offset letterboxes the
+/// image, a negative one crops it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Output size in character cells
+    pub cells: (u32, u32),
+    /// Canvas size in pixels
+    pub canvas: (u32, u32),
+    /// Size the source is scaled to, in pixels
+    pub scaled: (u32, u32),
+    pub offset: (i64, i64),
+}
+
+impl Layout {
+    /// Computes the layout of a `source` sized image
+    ///
+    /// `width` and `height` bound the output in cells; a missing one is derived from the other
+    /// so the aspect ratio is kept. `cell_aspect` is the height of a terminal cell divided by
+    /// its width and `cell_size` the pixels the renderer packs into one cell.
+    pub fn new(
+        source: (u32, u32),
+        (width, height): (Option<u32>, Option<u32>),
+        fit: FitMode,
+        cell_aspect: f64,
+        (cell_width, cell_height): (u32, u32),
+    ) -> Self {
+        let (source_width, source_height) = (source.0.max(1) as f64, source.1.max(1) as f64);
+        // Height of one renderer pixel relative to its width on screen
+        let pixel_aspect = cell_aspect * cell_width as f64 / cell_height as f64;
+        // Source height in renderer pixels per renderer pixel of width
+        let ratio = source_height / source_width / pixel_aspect;
+
+        let cells_for = |pixels: f64, cell: u32| ((pixels / cell as f64).round() as u32).max(1);
+        let exact = |cells: (u32, u32)| {
+            let canvas = (cells.0 * cell_width, cells.1 * cell_height);
+            Self {
+                cells,
+                canvas,
+                scaled: canvas,
+                offset: (0, 0),
+            }
+        };
+
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            (Some(width), None) => {
+                let pixels = (width * cell_width) as f64 * ratio;
+                return exact((width, cells_for(pixels, cell_height)));
+            }
+            (None, Some(height)) => {
+                let pixels = (height * cell_height) as f64 / ratio;
+                return exact((cells_for(pixels, cell_width), height));
+            }
+            (None, None) => unreachable!("at least one output dimension must be bounded"),
+        };
+
+        let layout = exact((width, height));
+        let (canvas_width, canvas_height) = (layout.canvas.0 as f64, layout.canvas.1 as f64);
+        let scaled_width = match fit {
+            FitMode::Stretch => return layout,
+            FitMode::Fit => canvas_width.min(canvas_height / ratio),
+            FitMode::Fill => canvas_width.max(canvas_height / ratio),
+        };
+        let scaled = (
+            (scaled_width.round() as u32).max(1),
+            ((scaled_width * ratio).round() as u32).max(1),
+        );
+
+        Self {
+            scaled,
+            offset: (
+                (layout.canvas.0 as i64 - scaled.0 as i64) / 2,
+                (layout.canvas.1 as i64 - scaled.1 as i64) / 2,
+            ),
+            ..layout
+        }
+    }
+
+    /// Places an image scaled to [`Layout::scaled`] onto the canvas
+    pub fn place<'a>(&self, scaled: &'a RgbImage) -> Cow<'a, RgbImage> {
+        if self.scaled == self.canvas {
+            return Cow::Borrowed(scaled);
+        }
+
+        let mut canvas = RgbImage::new(self.canvas.0, self.canvas.1);
+        imageops::replace(&mut canvas, scaled, self.offset.0, self.offset.1);
+        Cow::Owned(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cell sizes of the ASCII, half-block and braille renderers
+    const CELL_SIZES: [(u32, u32); 3] = [(1, 1), (1, 2), (2, 4)];
+
+    const SOURCE: (u32, u32) = (1920, 1080);
+
+    /// Height over width of the area an image covers on screen, with cells twice as tall as
+    /// they are wide
+    fn screen_ratio((width, height): (u32, u32), (cell_width, cell_height): (u32, u32)) -> f64 {
+        (height as f64 / cell_height as f64 * 2.0) / (width as f64 / cell_width as f64)
+    }
+
+    #[test]
+    fn one_bound_keeps_the_aspect_ratio_for_every_cell_size() {
+        for cell_size in CELL_SIZES {
+            let layout = Layout::new(SOURCE, (Some(100), None), FitMode::Fit, 2.0, cell_size);
+            assert_eq!(layout.cells, (100, 28), "cell size {cell_size:?}");
+            assert_eq!(layout.canvas, (100 * cell_size.0, 28 * cell_size.1));
+            assert_eq!((layout.scaled, layout.offset), (layout.canvas, (0, 0)));
+
+            let layout = Layout::new(SOURCE, (None, Some(28)), FitMode::Fit, 2.0, cell_size);
+            assert_eq!(layout.cells, (100, 28), "cell size {cell_size:?}");
+        }
+    }
+
+    #[test]
+    fn fit_letterboxes_inside_the_canvas() {
+        let layout = Layout::new(SOURCE, (Some(100), Some(20)), FitMode::Fit, 2.0, (1, 1));
+        assert_eq!(layout.cells, (100, 20));
+        assert_eq!(layout.scaled, (71, 20));
+        assert_eq!(layout.offset, (14, 0));
+
+        for cell_size in CELL_SIZES {
+            let layout = Layout::new(SOURCE, (Some(100), Some(20)), FitMode::Fit, 2.0, cell_size);
+            let (scaled, canvas) = (layout.scaled, layout.canvas);
+            assert!(
+                scaled.0 <= canvas.0 && scaled.1 == canvas.1,
+                "cell size {cell_size:?}"
+            );
+            assert!(layout.offset.0 >= 0 && layout.offset.1 == 0);
+            let ratio = screen_ratio(scaled, cell_size);
+            assert!((ratio - 1080.0 / 1920.0).abs() < 0.02, "ratio {ratio}");
+        }
+    }
+
+    #[test]
+    fn fill_covers_the_canvas_and_crops() {
+        let layout = Layout::new(SOURCE, (Some(100), Some(20)), FitMode::Fill, 2.0, (1, 1));
+        assert_eq!(layout.scaled, (100, 28));
+        assert_eq!(layout.offset, (0, -4));
+
+        for cell_size in CELL_SIZES {
+            let layout = Layout::new(SOURCE, (Some(100), Some(20)), FitMode::Fill, 2.0, cell_size);
+            let (scaled, canvas) = (layout.scaled, layout.canvas);
+            assert!(
+                scaled.0 == canvas.0 && scaled.1 >= canvas.1,
+                "cell size {cell_size:?}"
+            );
+            assert!(layout.offset.0 == 0 && layout.offset.1 <= 0);
+        }
+    }
+
+    #[test]
+    fn stretch_uses_the_whole_canvas() {
+        for cell_size in CELL_SIZES {
+            let layout = Layout::new(
+                SOURCE,
+                (Some(100), Some(20)),
+                FitMode::Stretch,
+                2.0,
+                cell_size,
+            );
+            assert_eq!(layout.canvas, (100 * cell_size.0, 20 * cell_size.1));
+            assert_eq!((layout.scaled, layout.offset), (layout.canvas, (0, 0)));
+        }
+    }
+}
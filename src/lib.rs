@@ -0,0 +1,45 @@
+//! Converts video to ASCII art and plays it in the terminal
+//!
+//! [`Converter`] turns any [`FrameSource`] into frames on disk and [`Player`] plays them, or
+//! any other source rendered on the fly, with audio and keyboard controls. To show ASCII
+//! video in another terminal UI, [`Player::frames`] yields the rendered [`Frame`]s without
+//! touching the terminal. [`ImageConverter`] renders still images.
+
+mod audio;
+mod clock;
+mod color;
+mod controls;
+mod convert;
+mod error;
+mod frame_loader;
+mod layout;
+mod manifest;
+mod pack;
+mod photo;
+mod play;
+mod render;
+mod screen;
+mod sink;
+mod source;
+mod tone;
+mod transcode;
+mod types;
+
+pub use convert::Converter;
+pub use error::{Cause, Error, Result, Warnings};
+pub use frame_loader::Frame;
+pub use manifest::Manifest;
+pub use photo::ImageConverter;
+pub use play::{Frames, Player};
+pub use sink::{FrameSink, SinkHeader};
+pub use source::{
+    FrameSource, Picture, SourceFrame, open_source,
+    scale::{FrameScaler, Unscaled},
+};
+pub use types::{
+    audio_format::AudioFormat, auto_levels::AutoLevels, charset::Charset, color_mode::ColorMode,
+    consts::DEFAULT_CONVERT_FPS, dither::Dither, fit_mode::FitMode, output_format::OutputFormat,
+    render_mode::RenderMode, render_options::RenderOptions, tone_options::ToneOptions,
+};
+
+use types::*;
@@ -1,20 +1,12 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
+use ascii4::{Converter, ImageConverter, OutputFormat, Player};
 use clap::{Parser, Subcommand};
-use std::{io::Write, time::Instant};
+use cli::{convert_args::ConvertArgs, image_args::ImageArgs, play_args::PlayArgs};
+use std::{collections::HashSet, fs, io::Write, path::PathBuf, time::Instant};
 
-mod convert;
-mod play;
-mod types;
-
-use convert::*;
-use convert_args::ConvertArgs;
-use play::*;
-use play_args::PlayArgs;
-use types::*;
+mod cli;
 
 // TODO: url for audio/video in args
-// TODO: use video (.mp4) for -a/--audio
-// TODO: photo convert
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Converts video to ASCII art and plays it", long_about = None)]
@@ -29,6 +21,92 @@ enum Commands {
     Convert(ConvertArgs),
     /// Play ASCII animation from frames directory
     Play(PlayArgs),
+    /// Convert still images to ASCII art
+    Image(ImageArgs),
+}
+
+/// Prints problems the library works around
+fn warn(message: &str) {
+    eprintln!("Warning: {message}");
+}
+
+/// Converts still images to ASCII, printing them or writing one text file per image
+fn run_image_conversion(args: ImageArgs) -> Result<()> {
+    let converter = ImageConverter::new()
+        .render(args.render.into())
+        .warnings(warn);
+    let paths = converter.collect(&args.input)?;
+    if paths.is_empty() {
+        return Err(anyhow!("No images found in {:?}", args.input));
+    }
+
+    let batch = paths.len() > 1;
+    // Every image written to a directory gets its full file name plus `.txt`, so `a.png` and
+    // `a.jpg` or `shot.v1.png` and `shot.v2.png` don't end up in the same file
+    let output_paths: Option<Vec<PathBuf>> = args.output.as_ref().map(|output| {
+        if !batch && !output.is_dir() {
+            return vec![output.clone()];
+        }
+        paths
+            .iter()
+            .map(|path| {
+                let mut name = path.file_name().unwrap_or(path.as_os_str()).to_os_string();
+                name.push(".txt");
+                output.join(name)
+            })
+            .collect()
+    });
+    if let Some(output_paths) = &output_paths {
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = output_paths.iter().find(|path| !seen.insert(*path)) {
+            return Err(anyhow!(
+                "Several images would be written to {duplicate:?}; convert them separately"
+            ));
+        }
+    }
+    if let Some(output_dir) = args.output.as_ref().filter(|_| batch) {
+        fs::create_dir_all(output_dir)
+            .with_context(|| format!("Failed to create output directory: {output_dir:?}"))?;
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    let mut failed = 0;
+    for (index, path) in paths.iter().enumerate() {
+        let ascii_art = match converter.convert(path) {
+            Ok(ascii_art) => ascii_art,
+            Err(e) if batch => {
+                eprintln!("Warning: {:#}", anyhow::Error::from(e));
+                failed += 1;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        match &output_paths {
+            None => {
+                if batch {
+                    writeln!(stdout, "==> {} <==", path.display())?;
+                }
+                writeln!(stdout, "{ascii_art}")?;
+            }
+            Some(output_paths) => {
+                let output_path = &output_paths[index];
+                fs::write(output_path, format!("{ascii_art}\n")).with_context(|| {
+                    format!("Failed to write ASCII art to file: {output_path:?}")
+                })?;
+                eprintln!("{} -> {}", path.display(), output_path.display());
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!(
+            "Converted {} of {} images",
+            paths.len() - failed,
+            paths.len()
+        );
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
@@ -39,6 +117,7 @@ fn main() -> Result<()> {
 
         let mut stdout = stdout();
 
+        let _ = terminal::disable_raw_mode();
         let _ = execute!(stdout, cursor::Show);
         let _ = execute!(stdout, terminal::LeaveAlternateScreen);
         let _ = stdout.flush();
@@ -51,13 +130,37 @@ fn main() -> Result<()> {
     let start_time = Instant::now();
 
     match cli.command {
+        Commands::Convert(args) if args.format == OutputFormat::Stdout => {
+            // stdout carries the frames themselves, so skip the status messages
+            Converter::from(args).warnings(warn).run()?;
+            return Ok(());
+        }
         Commands::Convert(args) => {
             println!("Starting conversion...");
-            run_conversion(args)?;
+            let manifest = Converter::from(args)
+                .warnings(warn)
+                .progress(|written_frames| {
+                    if written_frames % 10 == 0 {
+                        print!("\rProcessed ASCII frames: {written_frames}");
+                        std::io::stdout().flush().unwrap_or_default();
+                    }
+                })
+                .run()?;
+            println!("\rProcessed ASCII frames: {}", manifest.frame_count);
+            if let Some(audio) = &manifest.audio {
+                println!("Audio: {audio}");
+            }
         }
         Commands::Play(args) => {
             println!("Starting player...");
-            play_animation(args)?;
+            let input = args.input.as_ref().unwrap_or(&args.frames_dir);
+            println!("Opening: {}", input.display());
+            let dropped_frames = Player::from(args).warnings(warn).play()?;
+            eprintln!("Playback finished. Dropped frames: {dropped_frames}");
+        }
+        Commands::Image(args) => {
+            // stdout may carry the ASCII art itself, so skip the status messages
+            return run_image_conversion(args);
         }
     }
 
@@ -0,0 +1,194 @@
+use crate::types::{
+    color_mode::ColorMode,
+    consts::{MANIFEST_EXTENSION, MANIFEST_FILE},
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// Metadata `convert` records about its output so `play` can pick up the right settings
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    /// Version of the tool that did the conversion
+    pub version: String,
+    /// Video the frames were converted from, as given on the command line
+    pub source: String,
+    /// Duration of the source video in seconds, when known
+    pub duration: Option<f64>,
+    /// Frames per second the animation was converted at
+    pub fps: f64,
+    /// Frame size in character cells
+    pub width: u32,
+    pub height: u32,
+    /// Character ramp from darkest to lightest
+    pub charset: String,
+    pub color: ColorMode,
+    pub frame_count: u64,
+    /// Soundtrack file, relative to the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+}
+
+impl Manifest {
+    /// `manifest.json` inside a frames directory, or `<name>.json` next to a pack file
+    pub fn path_for(frames: &Path) -> PathBuf {
+        if frames.is_file() {
+            frames.with_extension(MANIFEST_EXTENSION)
+        } else {
+            frames.join(MANIFEST_FILE)
+        }
+    }
+
+    /// Reads the manifest of `frames`, if `convert` wrote one
+    pub fn load(frames: &Path) -> Result<Option<Self>> {
+        let path = Self::path_for(frames);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read manifest: {path:?}"))?;
+        let manifest = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse manifest: {path:?}"))?;
+        Ok(Some(manifest))
+    }
+
+    pub fn save(&self, frames: &Path) -> Result<()> {
+        let path = Self::path_for(frames);
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize manifest")?;
+        fs::write(&path, json).with_context(|| format!("Failed to write manifest: {path:?}"))
+    }
+
+    /// Resolves the soundtrack of `frames` relative to the manifest
+    pub fn audio_path(&self, frames: &Path) -> Option<PathBuf> {
+        let audio = self.audio.as_ref()?;
+        let manifest_path = Self::path_for(frames);
+        Some(manifest_path.parent().unwrap_or(Path::new("")).join(audio))
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} frames of {}x{} at {} fps, color {:?}, from {:?}",
+            self.frame_count, self.width, self.height, self.fps, self.color, self.source
+        )?;
+        if let Some(duration) = self.duration {
+            write!(f, " ({duration:.1}s)")?;
+        }
+        write!(f, ", converted by v{}", self.version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::Warnings,
+        pack::{PackHeader, PackWriter, encode_frame},
+        source::{FrameSource, frames::TextFrames},
+        types::consts::PACK_EXTENSION,
+    };
+
+    /// Frames directory unique to a test with a single frame, removed again when dropped
+    struct TempFrames(PathBuf);
+
+    impl TempFrames {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("ascii4-{}-{name}", std::process::id()));
+            fs::create_dir_all(dir.join("0")).unwrap();
+            fs::write(dir.join("0").join("1.txt"), "ab\ncd").unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempFrames {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn manifest(audio: Option<&str>) -> Manifest {
+        Manifest {
+            version: "0.3.0".to_string(),
+            source: "clip.mp4".to_string(),
+            duration: Some(1.5),
+            fps: 12.5,
+            width: 2,
+            height: 2,
+            charset: " .:#".to_string(),
+            color: ColorMode::Ansi256,
+            frame_count: 1,
+            audio: audio.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn manifests_read_back_what_was_written() {
+        let frames = TempFrames::new("manifest-round-trip");
+        assert!(Manifest::load(&frames.0).unwrap().is_none());
+
+        manifest(Some("clip.ogg")).save(&frames.0).unwrap();
+        assert!(frames.0.join(MANIFEST_FILE).is_file());
+
+        let loaded = Manifest::load(&frames.0).unwrap().unwrap();
+        assert_eq!(loaded.to_string(), manifest(None).to_string());
+        assert_eq!(loaded.charset, " .:#");
+        assert_eq!(loaded.color, ColorMode::Ansi256);
+        assert_eq!(loaded.audio.as_deref(), Some("clip.ogg"));
+        assert_eq!(
+            loaded.audio_path(&frames.0),
+            Some(frames.0.join("clip.ogg"))
+        );
+    }
+
+    #[test]
+    fn packs_keep_their_manifest_next_to_them() {
+        let frames = TempFrames::new("manifest-pack");
+        let pack = frames.0.join(format!("clip.{PACK_EXTENSION}"));
+        let header = PackHeader {
+            fps: 30.0,
+            width: 2,
+            height: 2,
+            charset: " .:#".to_string(),
+            color: ColorMode::Ansi256,
+            compressed: false,
+            frame_count: 0,
+        };
+        let mut writer = PackWriter::create(&pack, &header).unwrap();
+        writer
+            .write_frame(0, encode_frame("ab\ncd", false).unwrap())
+            .unwrap();
+        writer.finish().unwrap();
+
+        let path = Manifest::path_for(&pack);
+        assert_eq!(path, frames.0.join("clip.json"));
+        manifest(Some("clip.ogg")).save(&pack).unwrap();
+        assert!(path.is_file());
+
+        let loaded = Manifest::load(&pack).unwrap().unwrap();
+        assert_eq!(loaded.audio_path(&pack), Some(frames.0.join("clip.ogg")));
+
+        let source = TextFrames::open_pack(&pack, None, &Warnings::default()).unwrap();
+        assert_eq!(source.audio_path(), Some(frames.0.join("clip.ogg")));
+    }
+
+    #[test]
+    fn given_rates_override_the_recorded_one() {
+        let frames = TempFrames::new("manifest-fps");
+        manifest(Some("clip.ogg")).save(&frames.0).unwrap();
+
+        let recorded = TextFrames::open_directory(&frames.0, None, &Warnings::default()).unwrap();
+        assert_eq!(recorded.fps(), Some(12.5));
+        assert_eq!(recorded.audio_path(), Some(frames.0.join("clip.ogg")));
+
+        let given =
+            TextFrames::open_directory(&frames.0, Some(24.0), &Warnings::default()).unwrap();
+        assert_eq!(given.fps(), Some(24.0));
+        assert_eq!(given.duration(), Some(1.0 / 24.0));
+    }
+}
@@ -0,0 +1,374 @@
+//! Single-file container for ASCII animations
+//!
+//! Layout (all integers little-endian):
+//!
+//! ```text
+//! magic          8 bytes  "ASCII4PK"
+//! version        u16
+//! flags          u16      bit 0: frame payloads are zstd-compressed
+//! fps            f64
+//! width, height  u32, u32 frame size in character cells
+//! frame count    u64
+//! index offset   u64      absolute offset of the frame index
+//! charset        u32 length + UTF-8 bytes
+//! color mode     u32 length + UTF-8 bytes
+//! payloads       frame data, in playback order
+//! index          frame count x (offset u64, length u32)
+//! ```
+//!
+//! The index lives at the end so frames can be streamed out before their number is known;
+//! the frame count and index offset are patched into the header when the writer finishes.
+
+use crate::color_mode::ColorMode;
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"ASCII4PK";
+const VERSION: u16 = 1;
+const FLAG_ZSTD: u16 = 1;
+/// Position of the frame count field, right after magic, version, flags, fps and size
+const FRAME_COUNT_OFFSET: u64 = 28;
+const ZSTD_LEVEL: i32 = 3;
+
+/// Animation properties stored at the start of a container
+#[derive(Clone, Debug)]
+pub struct PackHeader {
+    pub fps: f64,
+    pub width: u32,
+    pub height: u32,
+    pub charset: String,
+    pub color: ColorMode,
+    pub compressed: bool,
+    pub frame_count: u64,
+}
+
+/// Encodes one frame as stored in a container, compressing it when requested
+pub fn encode_frame(content: &str, compressed: bool) -> Result<Vec<u8>> {
+    if compressed {
+        zstd::encode_all(content.as_bytes(), ZSTD_LEVEL).context("Failed to compress frame")
+    } else {
+        Ok(content.as_bytes().to_vec())
+    }
+}
+
+fn write_str(out: &mut impl Write, value: &str) -> Result<()> {
+    out.write_all(&(value.len() as u32).to_le_bytes())?;
+    out.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> Result<[u8; N]> {
+    let mut buf = [0; N];
+    input.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_str(input: &mut impl Read) -> Result<String> {
+    let len = u32::from_le_bytes(read_array(input)?) as usize;
+    let mut buf = vec![0; len];
+    input.read_exact(&mut buf)?;
+    String::from_utf8(buf).context("Invalid UTF-8 in container header")
+}
+
+/// Writes frames into a container in playback order
+///
+/// Frames may be submitted out of order by parallel workers; they are held back until every
+/// earlier frame has been written.
+pub struct PackWriter {
+    file: BufWriter<File>,
+    offset: u64,
+    index: Vec<(u64, u32)>,
+    pending: BTreeMap<u64, Vec<u8>>,
+}
+
+impl PackWriter {
+    pub fn create(path: &Path, header: &PackHeader) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create container file: {path:?}"))?;
+        let mut file = BufWriter::new(file);
+
+        let flags = if header.compressed { FLAG_ZSTD } else { 0 };
+        let color = header
+            .color
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.write_all(&flags.to_le_bytes())?;
+        file.write_all(&header.fps.to_le_bytes())?;
+        file.write_all(&header.width.to_le_bytes())?;
+        file.write_all(&header.height.to_le_bytes())?;
+        // Frame count and index offset are patched in by `finish`
+        file.write_all(&0u64.to_le_bytes())?;
+        file.write_all(&0u64.to_le_bytes())?;
+        write_str(&mut file, &header.charset)?;
+        write_str(&mut file, &color)?;
+
+        let offset = file.stream_position()?;
+        Ok(Self {
+            file,
+            offset,
+            index: Vec::new(),
+            pending: BTreeMap::new(),
+        })
+    }
+
+    /// Queues the encoded payload of frame `number`, writing every frame that is now in order
+    ///
+    /// A payload stays queued until it has been written, so a failed write loses nothing.
+    pub fn write_frame(&mut self, number: u64, payload: Vec<u8>) -> Result<()> {
+        self.pending.insert(number, payload);
+
+        loop {
+            let next = self.index.len() as u64;
+            let Some(payload) = self.pending.get(&next) else {
+                return Ok(());
+            };
+            let len: u32 = payload
+                .len()
+                .try_into()
+                .context("Frame too large for container")?;
+            self.file.write_all(payload)?;
+            self.index.push((self.offset, len));
+            self.offset += len as u64;
+            self.pending.remove(&next);
+        }
+    }
+
+    /// Writes the index and patches the header, returning the number of frames
+    pub fn finish(mut self) -> Result<u64> {
+        if let Some(number) = self.pending.keys().next() {
+            return Err(anyhow!(
+                "Container is missing frame {} (next queued frame is {number})",
+                self.index.len()
+            ));
+        }
+
+        let index_offset = self.offset;
+        for (offset, len) in &self.index {
+            self.file.write_all(&offset.to_le_bytes())?;
+            self.file.write_all(&len.to_le_bytes())?;
+        }
+
+        let frame_count = self.index.len() as u64;
+        self.file.seek(SeekFrom::Start(FRAME_COUNT_OFFSET))?;
+        self.file.write_all(&frame_count.to_le_bytes())?;
+        self.file.write_all(&index_offset.to_le_bytes())?;
+        self.file.flush()?;
+
+        Ok(frame_count)
+    }
+}
+
+/// Whether `path` is a file starting with the container magic
+pub fn is_pack(path: &Path) -> bool {
+    File::open(path)
+        .and_then(|mut file| {
+            let mut magic = [0; MAGIC.len()];
+            file.read_exact(&mut magic)?;
+            Ok(&magic == MAGIC)
+        })
+        .unwrap_or(false)
+}
+
+/// Random-access reader for a container
+pub struct PackReader {
+    file: BufReader<File>,
+    header: PackHeader,
+    index: Vec<(u64, u32)>,
+}
+
+impl PackReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open container: {path:?}"))?;
+        let mut file = BufReader::new(file);
+
+        if &read_array::<8>(&mut file)? != MAGIC {
+            return Err(anyhow!("Not an ascii4 container: {path:?}"));
+        }
+        let version = u16::from_le_bytes(read_array(&mut file)?);
+        if version != VERSION {
+            return Err(anyhow!("Unsupported container version {version}: {path:?}"));
+        }
+        let flags = u16::from_le_bytes(read_array(&mut file)?);
+        let fps = f64::from_le_bytes(read_array(&mut file)?);
+        let width = u32::from_le_bytes(read_array(&mut file)?);
+        let height = u32::from_le_bytes(read_array(&mut file)?);
+        let frame_count = u64::from_le_bytes(read_array(&mut file)?);
+        let index_offset = u64::from_le_bytes(read_array(&mut file)?);
+        let charset = read_str(&mut file)?;
+        let color = ColorMode::from_str(&read_str(&mut file)?, true).unwrap_or_default();
+
+        file.seek(SeekFrom::Start(index_offset))?;
+        let index = (0..frame_count)
+            .map(|_| -> Result<(u64, u32)> {
+                let offset = u64::from_le_bytes(read_array(&mut file)?);
+                let len = u32::from_le_bytes(read_array(&mut file)?);
+                Ok((offset, len))
+            })
+            .collect::<Result<_>>()
+            .with_context(|| format!("Truncated container index: {path:?}"))?;
+
+        Ok(Self {
+            file,
+            header: PackHeader {
+                fps,
+                width,
+                height,
+                charset,
+                color,
+                compressed: flags & FLAG_ZSTD != 0,
+                frame_count,
+            },
+            index,
+        })
+    }
+
+    pub fn header(&self) -> &PackHeader {
+        &self.header
+    }
+
+    /// Reads and decodes frame `number`
+    pub fn read_frame(&mut self, number: usize) -> Result<String> {
+        let (offset, len) = *self
+            .index
+            .get(number)
+            .ok_or_else(|| anyhow!("Frame {number} is out of range"))?;
+
+        let mut payload = vec![0; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut payload)?;
+
+        if self.header.compressed {
+            payload = zstd::decode_all(payload.as_slice())
+                .with_context(|| format!("Failed to decompress frame {number}"))?;
+        }
+        String::from_utf8(payload).with_context(|| format!("Invalid UTF-8 in frame {number}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::consts::PACK_EXTENSION;
+    use std::{fs, path::PathBuf};
+
+    /// Container path unique to a test, removed again when dropped
+    struct TempPack(PathBuf);
+
+    impl TempPack {
+        fn new(name: &str) -> Self {
+            let name = format!("ascii4-{}-{name}.{}", std::process::id(), PACK_EXTENSION);
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempPack {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn header(compressed: bool) -> PackHeader {
+        PackHeader {
+            fps: 29.97,
+            width: 4,
+            height: 2,
+            charset: " .:#".to_string(),
+            color: ColorMode::Ansi256,
+            compressed,
+            frame_count: 0,
+        }
+    }
+
+    fn round_trip(compressed: bool) {
+        let pack = TempPack::new(if compressed { "zstd" } else { "plain" });
+        let frames = ["ab\ncd", "\x1b[38;5;1mef\x1b[0m\ngh", "", "ij\nkl"];
+
+        let mut writer = PackWriter::create(&pack.0, &header(compressed)).unwrap();
+        // Workers finish frames in any order
+        for number in [2, 0, 3, 1] {
+            let payload = encode_frame(frames[number], compressed).unwrap();
+            writer.write_frame(number as u64, payload).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), frames.len() as u64);
+
+        assert!(is_pack(&pack.0));
+        let mut reader = PackReader::open(&pack.0).unwrap();
+        let read = reader.header().clone();
+        assert_eq!(read.fps, 29.97);
+        assert_eq!((read.width, read.height), (4, 2));
+        assert_eq!(read.charset, " .:#");
+        assert_eq!(read.color, ColorMode::Ansi256);
+        assert_eq!(read.compressed, compressed);
+        assert_eq!(read.frame_count, frames.len() as u64);
+        for (number, frame) in frames.iter().enumerate() {
+            assert_eq!(reader.read_frame(number).unwrap(), *frame);
+        }
+        assert!(reader.read_frame(frames.len()).is_err());
+    }
+
+    #[test]
+    fn frames_written_out_of_order_read_back_in_order() {
+        round_trip(false);
+    }
+
+    #[test]
+    fn compressed_frames_read_back_unchanged() {
+        round_trip(true);
+    }
+
+    #[test]
+    fn finishing_with_a_missing_frame_fails() {
+        let pack = TempPack::new("gap");
+        let mut writer = PackWriter::create(&pack.0, &header(false)).unwrap();
+        writer.write_frame(0, b"a".to_vec()).unwrap();
+        writer.write_frame(2, b"c".to_vec()).unwrap();
+        assert!(writer.finish().is_err());
+    }
+
+    /// Writes a two-frame container and returns its bytes
+    fn written(pack: &TempPack) -> Vec<u8> {
+        let mut writer = PackWriter::create(&pack.0, &header(false)).unwrap();
+        writer.write_frame(0, b"a".to_vec()).unwrap();
+        writer.write_frame(1, b"b".to_vec()).unwrap();
+        writer.finish().unwrap();
+        fs::read(&pack.0).unwrap()
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let pack = TempPack::new("version");
+        let mut bytes = written(&pack);
+        bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        fs::write(&pack.0, bytes).unwrap();
+
+        let error = PackReader::open(&pack.0).err().unwrap();
+        assert!(
+            error.to_string().contains("Unsupported container version"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn truncated_indexes_are_rejected() {
+        let pack = TempPack::new("truncated");
+        let bytes = written(&pack);
+        fs::write(&pack.0, &bytes[..bytes.len() - 4]).unwrap();
+
+        let error = PackReader::open(&pack.0).err().unwrap();
+        assert!(
+            error.to_string().contains("Truncated container index"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error::{Error, Warnings};
+use crate::render::{Renderer, renderer_for};
+use crate::source::images::collect_images;
+use crate::tone;
+use crate::types::render_options::RenderOptions;
+use anyhow::Context;
+use image::{RgbImage, imageops::FilterType};
+use std::path::{Path, PathBuf};
+
+/// Renders still images to ASCII art
+///
+/// ```no_run
+/// use ascii4::ImageConverter;
+///
+/// let converter = ImageConverter::new();
+/// for path in converter.collect(&["photos/*.png".to_string()])? {
+///     println!("{}", converter.convert(&path)?);
+/// }
+/// # Ok::<(), ascii4::Error>(())
+/// ```
+pub struct ImageConverter {
+    render: RenderOptions,
+    renderer: Box<dyn Renderer>,
+    warnings: Warnings,
+}
+
+impl Default for ImageConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImageConverter {
+    /// Converter with the default sizing and glyph options
+    pub fn new() -> Self {
+        let render = RenderOptions::default();
+        Self {
+            renderer: renderer_for(&render),
+            render,
+            warnings: Warnings::default(),
+        }
+    }
+
+    /// Sizing and glyph options
+    pub fn render(self, render: RenderOptions) -> Self {
+        Self {
+            renderer: renderer_for(&render),
+            render,
+            ..self
+        }
+    }
+
+    /// Reports problems that don't stop a conversion, such as a glob match that can't be read
+    pub fn warnings(mut self, handler: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.warnings = Warnings::new(handler);
+        self
+    }
+
+    /// Expands files, directories of images and glob patterns into the image files they name
+    pub fn collect(&self, inputs: &[String]) -> crate::Result<Vec<PathBuf>> {
+        collect_images(inputs, &self.warnings).map_err(Error::input)
+    }
+
+    /// Loads the image at `path` and renders it
+    pub fn convert(&self, path: impl AsRef<Path>) -> crate::Result<String> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .with_context(|| format!("Failed to read image: {path:?}"))
+            .map_err(Error::input)?
+            .to_rgb8();
+        self.convert_image(&image)
+    }
+
+    /// Renders an image laid out according to the sizing options
+    pub fn convert_image(&self, image: &RgbImage) -> crate::Result<String> {
+        self.render.tone.validate()?;
+        // Every image gets its own layout, since their aspect ratios differ
+        let layout = self.render.layout(
+            image.dimensions(),
+            self.renderer.cell_size(),
+            self.render.auto_size,
+            &self.warnings,
+        )?;
+        let (scaled_width, scaled_height) = layout.scaled;
+        let scaled =
+            image::imageops::resize(image, scaled_width, scaled_height, FilterType::Triangle);
+        let toned = tone::adjust(&scaled, &self.render.tone);
+        Ok(self.renderer.render(&layout.place(&toned)))
+    }
+}
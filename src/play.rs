@@ -1,210 +1,535 @@
-use crate::info::{FrameInfo, SecondInfo};
-use crate::play_args::PlayArgs;
+use crate::audio::AudioSource;
+use crate::clock::PlaybackClock;
+use crate::consts::{DEFAULT_PLAY_FPS, PAUSED_POLL_INTERVAL, READ_AHEAD_FRAMES};
+use crate::controls::{Action, MAX_SPEED, MIN_SPEED, poll_action};
+use crate::error::{Error, Warnings};
+use crate::frame_loader::{Frame, FrameLoader};
+use crate::layout::Layout;
+use crate::render::renderer_for;
+use crate::render_options::RenderOptions;
+use crate::screen::Screen;
+use crate::source::{FrameSource, Input};
 use crate::terminal_guard::TerminalGuard;
-use anyhow::{Context, Result, anyhow};
-use crossterm::{cursor, execute, terminal};
-use rodio::{Decoder, OutputStream, Sink, Source};
+use anyhow::Result;
+use rodio::{OutputStream, Sink};
 use std::{
-    fs::{self, File},
-    io::{BufReader, Write, stdout},
+    mem,
     path::{Path, PathBuf},
-    time::{Duration, Instant},
+    time::Duration,
 };
-use sysx::time::safe_sleep;
 
-fn initialize_audio(options: &PlayArgs) -> Result<(Sink, OutputStream)> {
+fn initialize_audio(audio: Option<&Path>, warnings: &Warnings) -> Result<(Sink, OutputStream)> {
     let (stream, stream_handle) = OutputStream::try_default()?;
     let sink = Sink::try_new(&stream_handle)?;
+    // Held until the first frame is ready so that frame discovery doesn't eat into the sync
+    sink.pause();
 
-    if let Some(path) = &options.audio {
-        load_audio_file(&sink, path).unwrap_or_else(|e| eprintln!("Audio loading error: {e}"));
+    if let Some(path) = audio {
+        load_audio_file(&sink, path, warnings)
+            .unwrap_or_else(|e| warnings.warn(format_args!("Audio loading error: {e}")));
     }
 
     Ok((sink, stream))
 }
 
-fn load_audio_file(sink: &Sink, path: &Path) -> Result<()> {
-    let file = BufReader::new(File::open(path)?);
-    let source = Decoder::new(file)?.convert_samples::<f32>();
-    sink.append(source);
+/// Queues the best audio stream of any file ffmpeg can demux, including video containers
+fn load_audio_file(sink: &Sink, path: &Path, warnings: &Warnings) -> Result<()> {
+    sink.append(AudioSource::open(path, warnings.clone())?);
     Ok(())
 }
 
-fn render_frame(content: &str) -> Result<()> {
-    execute!(
-        stdout(),
-        terminal::Clear(terminal::ClearType::All),
-        cursor::MoveTo(0, 0),
-    )?;
-    write!(stdout(), "{content}")?;
-    stdout().flush()?;
-    Ok(())
+/// What a source rendered on the fly needs to be laid out again for a new terminal size
+struct Rescale {
+    render: RenderOptions,
+    source: (u32, u32),
+    cell_size: (u32, u32),
+    layout: Layout,
+}
+
+/// Playback position and the state the keyboard controls act on
+struct Playback<'a> {
+    frames: FrameLoader,
+    screen: Screen,
+    /// Set when frames are rendered during playback and can follow the terminal size
+    rescale: Option<Rescale>,
+    clock: PlaybackClock<'a>,
+    fps: f64,
+    frame_count: u64,
+    /// Whether timeline positions wrap around the animation (looping without audio restarts)
+    wraps: bool,
+    /// Position of the next frame on the clock's timeline, in frames since the clock started
+    scheduled_frame: u64,
+    /// Frame fetched from the loader but not due yet
+    pending: Option<Frame>,
+    /// Show the next frame immediately, even while paused
+    step_requested: bool,
 }
 
-/// Main animation playback function
-pub fn play_animation(options: PlayArgs) -> Result<()> {
-    if options.fps <= 0.0 {
-        return Err(anyhow!("FPS must be positive"));
+impl Playback<'_> {
+    fn frame_time(&self, frame: u64) -> Duration {
+        Duration::from_secs_f64(frame as f64 / self.fps)
     }
 
-    let _terminal_guard = TerminalGuard::new();
+    /// Position in the animation of `frame` on the timeline; frames of an animation of
+    /// unknown length are never wrapped
+    fn index_of(&self, frame: u64) -> usize {
+        match self.frame_count {
+            0 => frame as usize,
+            count => (frame % count) as usize,
+        }
+    }
 
-    let (sink, _stream) = initialize_audio(&options)?;
+    /// Moves the timeline to `frame`, requesting that it is shown right away
+    ///
+    /// Does nothing when the source can't seek.
+    fn jump_to(&mut self, frame: u64) {
+        if !self.frames.can_seek() {
+            return;
+        }
+        let frame = if self.wraps || self.frame_count == 0 {
+            frame
+        } else {
+            frame.min(self.frame_count - 1)
+        };
+        let index = self.index_of(frame);
 
-    println!("Scanning frames directory: {:?}", options.frames_dir);
-    let ordered_frames = discover_and_sort_frames(&options.frames_dir)?;
+        self.frames.seek(index);
+        self.pending = None;
+        self.scheduled_frame = frame;
+        self.clock.seek(self.frame_time(frame));
+        self.step_requested = true;
+    }
+
+    /// Adapts to a terminal of `columns` x `rows` cells
+    ///
+    /// Pre-rendered frames are re-centered or cropped as they are, while a video decoded on
+    /// the fly is rendered again at the new size from the frame on screen onwards.
+    fn resize(&mut self, columns: u16, rows: u16) -> crate::Result<()> {
+        if let Some(rescale) = &mut self.rescale {
+            let layout = rescale.render.layout_within(
+                rescale.source,
+                rescale.cell_size,
+                Some((columns, rows)),
+            )?;
+            if layout != rescale.layout && !self.frames.can_seek() {
+                // The stream goes on from where it is, so only later frames get the new size
+                rescale.layout = layout;
+                self.frames.relayout(layout, 0);
+            } else if layout != rescale.layout {
+                rescale.layout = layout;
+                let paused = self.clock.is_paused();
+                // While playing, the frame on screen is already behind the one scheduled next
+                let frame = if paused {
+                    self.scheduled_frame.saturating_sub(1)
+                } else {
+                    self.scheduled_frame
+                };
+                let index = self.index_of(frame);
 
-    if ordered_frames.is_empty() {
-        return Err(anyhow!(
-            "No valid frame files found in directory structure: {:?}",
-            options.frames_dir
-        ));
+                self.frames.relayout(layout, index);
+                self.pending = None;
+                self.scheduled_frame = frame;
+                self.step_requested |= paused;
+            }
+        }
+        self.screen.redraw().map_err(Error::terminal)
     }
-    println!(
-        "Found {} frames. Target FPS: {}",
-        ordered_frames.len(),
-        options.fps
-    );
 
-    let frame_contents: Vec<String> = ordered_frames
-        .iter()
-        .map(|path| {
-            fs::read_to_string(path).context(format!("Failed to read frame file: {path:?}"))
-        })
-        .collect::<Result<_, _>>()?;
-
-    let playback_loop = || -> Result<()> {
-        if options.sync {
-            sink.stop();
-            if let Some(path) = &options.audio {
-                load_audio_file(&sink, path)
-                    .unwrap_or_else(|e| eprintln!("Audio reload error: {e}"));
+    /// Applies a keyboard action, returning `true` when playback should stop
+    fn apply(&mut self, action: Action) -> crate::Result<bool> {
+        match action {
+            Action::Quit => return Ok(true),
+            Action::TogglePause => {
+                if self.clock.is_paused() {
+                    // Resume from the frame that is next in line
+                    self.clock.seek(self.frame_time(self.scheduled_frame));
+                    self.clock.resume();
+                } else {
+                    self.clock.pause();
+                }
+            }
+            Action::Seek(seconds) => {
+                let target = (self.clock.now().as_secs_f64() + seconds).max(0.0);
+                self.jump_to((target * self.fps) as u64);
+            }
+            Action::Speed(delta) => {
+                let speed = (self.clock.speed() + delta).clamp(MIN_SPEED, MAX_SPEED);
+                self.clock.set_speed(speed);
+            }
+            Action::StepForward if self.clock.is_paused() => {
+                self.step_requested = true;
             }
+            Action::StepBack if self.clock.is_paused() => {
+                // The frame on screen is `scheduled_frame - 1`, so go one before that
+                self.jump_to(self.scheduled_frame.saturating_sub(2));
+            }
+            Action::StepForward | Action::StepBack => {}
+            Action::Resize(columns, rows) => self.resize(columns, rows)?,
         }
+        Ok(false)
+    }
+}
+
+/// Plays converted frames, or any other frame source rendered on the fly, in the terminal
+///
+/// ```no_run
+/// use ascii4::Player;
+///
+/// Player::new("output").looping(true).info(true).play()?;
+/// # Ok::<(), ascii4::Error>(())
+/// ```
+pub struct Player {
+    source: Input,
+    fps: Option<f64>,
+    audio: Option<PathBuf>,
+    looping: bool,
+    sync: bool,
+    info: bool,
+    render: RenderOptions,
+    warnings: Warnings,
+}
 
-        let frame_duration = Duration::from_secs_f64(1.0 / options.fps);
-        let mut last_frame_time = Instant::now();
+/// Frames a [`Player`] opened, ready to be shown at `fps`
+struct Opened {
+    frames: FrameLoader,
+    fps: f64,
+    charset: Option<String>,
+    audio: Option<PathBuf>,
+    rescale: Option<Rescale>,
+}
 
-        for content in &frame_contents {
-            render_frame(content)?;
+impl Player {
+    /// Plays `source`: frames `convert` wrote to a directory or pack file, or anything else
+    /// [`open_source`](crate::open_source) reads, such as a video or `-` for standard input
+    pub fn new(source: impl Into<PathBuf>) -> Self {
+        Self::with_input(Input::Path(source.into()))
+    }
 
-            let elapsed = last_frame_time.elapsed();
-            let sleep_duration = frame_duration.saturating_sub(elapsed);
-            safe_sleep(sleep_duration)?;
+    /// Plays frames from a source opened by the caller
+    pub fn from_source(source: Box<dyn FrameSource>) -> Self {
+        Self::with_input(Input::Source(source))
+    }
 
-            last_frame_time = Instant::now();
+    fn with_input(source: Input) -> Self {
+        Self {
+            source,
+            fps: None,
+            audio: None,
+            looping: false,
+            sync: false,
+            info: false,
+            render: RenderOptions::default(),
+            warnings: Warnings::default(),
         }
-        Ok(())
-    };
+    }
 
-    if options.loop_gif {
-        loop {
-            playback_loop()?;
-            safe_sleep("10ms")?;
+    /// Playback FPS; defaults to the rate of the source, or 30
+    pub fn fps(mut self, fps: f64) -> Self {
+        self.fps = Some(fps);
+        self
+    }
+
+    /// Audio file, or video file whose audio track is played along
+    pub fn audio(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audio = Some(path.into());
+        self
+    }
+
+    /// Wraps around to the first frame at the end, like a GIF; sources that can't seek play once
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Restarts the audio with every loop; requires [`Player::looping`]
+    pub fn sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Shows an overlay with the frame counter, FPS and character ramp
+    pub fn info(mut self, info: bool) -> Self {
+        self.info = info;
+        self
+    }
+
+    /// Sizing and glyph options for sources that aren't rendered yet; the size defaults to
+    /// the terminal
+    pub fn render(mut self, render: RenderOptions) -> Self {
+        self.render = render;
+        self
+    }
+
+    /// Reports problems that don't stop playback, such as a soundtrack that can't be loaded
+    ///
+    /// The handler is called while the player has the terminal, so it shouldn't draw to it.
+    pub fn warnings(mut self, handler: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.warnings = Warnings::new(handler);
+        self
+    }
+
+    /// Opens the frames without taking over the terminal, to show them in another UI
+    ///
+    /// Frames are yielded as fast as they are read or rendered; pacing them at
+    /// [`Frames::fps`] is up to the caller.
+    pub fn frames(mut self) -> crate::Result<Frames> {
+        let opened = self.open()?;
+        Ok(Frames {
+            loader: opened.frames,
+            fps: opened.fps,
+        })
+    }
+
+    /// Settings recorded with the source act as defaults for the ones not given here
+    fn open(&mut self) -> crate::Result<Opened> {
+        if self.sync && !self.looping {
+            return Err(Error::InvalidOption(
+                "Syncing audio requires looping".to_string(),
+            ));
         }
-    } else {
-        playback_loop()?;
+        if self.fps.is_some_and(|fps| fps <= 0.0) {
+            return Err(Error::InvalidOption("FPS must be positive".to_string()));
+        }
+        self.render.tone.validate()?;
+
+        let input = mem::replace(&mut self.source, Input::Path(PathBuf::new()));
+        let source = input.open(self.fps, &self.warnings).map_err(Error::input)?;
+        let fps = self
+            .fps
+            .or(source.fps())
+            .filter(|fps| *fps > 0.0)
+            .unwrap_or(DEFAULT_PLAY_FPS);
+        let audio = self.audio.clone().or(source.audio_path());
+
+        let charset = self.render.ramp();
+        let ramp: String = charset.ramp().iter().collect();
+        let renderer = renderer_for(&self.render);
+        let layout =
+            self.render
+                .layout(source.size(), renderer.cell_size(), true, &self.warnings)?;
+
+        // Text frames are shown as they were rendered, anything else follows the terminal size
+        let (charset, rescale) = if source.is_text() {
+            (source.charset(), None)
+        } else {
+            let rescale = Rescale {
+                render: self.render.clone(),
+                source: source.size(),
+                cell_size: renderer.cell_size(),
+                layout,
+            };
+            (Some(ramp), Some(rescale))
+        };
+
+        let frames = FrameLoader::spawn(
+            source,
+            renderer,
+            layout,
+            self.render.tone,
+            fps,
+            READ_AHEAD_FRAMES,
+            self.looping,
+        );
+        Ok(Opened {
+            frames,
+            fps,
+            charset,
+            audio,
+            rescale,
+        })
     }
 
-    sink.stop();
-    println!("\nPlayback finished.");
+    /// Plays in the terminal with audio and keyboard controls until the end or `q`
+    ///
+    /// Returns the number of frames dropped to keep up with the clock, once the terminal has
+    /// been restored.
+    pub fn play(mut self) -> crate::Result<u64> {
+        let Opened {
+            frames,
+            fps,
+            charset,
+            audio,
+            rescale,
+        } = self.open()?;
 
-    Ok(())
-}
+        let (sink, _stream) =
+            initialize_audio(audio.as_deref(), &self.warnings).map_err(Error::audio)?;
 
-/// Discovers and sorts frame files in directory
-fn discover_and_sort_frames(base_dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut seconds: Vec<SecondInfo> = Vec::new();
+        let _terminal_guard = TerminalGuard::new();
 
-    let entries = fs::read_dir(base_dir)
-        .with_context(|| format!("Failed to read base directory: {base_dir:?}"))?;
+        // Only a source that can go back to the start is looped
+        let wraps = self.looping && !self.sync && frames.can_seek();
+        let mut playback = Playback {
+            frame_count: frames.len() as u64,
+            frames,
+            screen: Screen::new(),
+            rescale,
+            clock: PlaybackClock::new(audio.as_ref().map(|_| &sink)),
+            fps,
+            wraps,
+            scheduled_frame: 0,
+            pending: None,
+            step_requested: false,
+        };
+        let mut dropped_frames: u64 = 0;
+        sink.play();
+        playback.clock.restart();
 
-    for entry_res in entries {
-        let entry = entry_res?;
-        let path = entry.path();
+        loop {
+            if playback.clock.is_paused() && !playback.step_requested {
+                if let Some(action) = poll_action(PAUSED_POLL_INTERVAL).map_err(Error::terminal)?
+                    && playback.apply(action)?
+                {
+                    break;
+                }
+                continue;
+            }
 
-        if path.is_dir() {
-            if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                if let Ok(second_num) = dir_name.parse::<u64>() {
-                    let mut current_second = SecondInfo {
-                        number: second_num,
-                        frames: Vec::new(),
+            let frame = match playback.pending.take() {
+                Some(frame) => frame,
+                None => {
+                    let Some(frame) = playback.frames.next_frame() else {
+                        break;
                     };
+                    let frame = frame.map_err(Error::input)?;
 
-                    for frame_entry_res in fs::read_dir(&path)
-                        .with_context(|| format!("Failed to read second directory: {path:?}"))?
-                    {
-                        let frame_entry = frame_entry_res?;
-                        let frame_path = frame_entry.path();
-
-                        if frame_path.is_file()
-                            && frame_path.extension().is_some_and(|ext| ext == "txt")
-                        {
-                            if let Some(frame_stem) =
-                                frame_path.file_stem().and_then(|s| s.to_str())
-                            {
-                                if let Ok(frame_num) = frame_stem.parse::<u64>() {
-                                    current_second.frames.push(FrameInfo {
-                                        path: frame_path,
-                                        number: frame_num,
-                                    });
-                                } else {
-                                    eprintln!(
-                                        "Warning: Could not parse frame number from file name: {frame_path:?}"
-                                    );
-                                }
-                            }
+                    if frame.index == 0 && self.sync && !playback.clock.is_paused() {
+                        sink.stop();
+                        if let Some(path) = &audio {
+                            load_audio_file(&sink, path, &self.warnings).unwrap_or_else(|e| {
+                                self.warnings.warn(format_args!("Audio reload error: {e}"))
+                            });
                         }
+                        playback.clock.restart();
+                        playback.scheduled_frame = 0;
                     }
-                    current_second.frames.sort_by_key(|f| f.number);
+                    frame
+                }
+            };
 
-                    if !current_second.frames.is_empty() {
-                        seconds.push(current_second);
-                    }
-                } else {
-                    eprintln!("Warning: Directory name is not a valid second number: {path:?}");
+            if !playback.step_requested {
+                let due = playback.frame_time(playback.scheduled_frame);
+                let now = playback.clock.now();
+
+                // Already past this frame's slot: skip it to catch up instead of drifting behind
+                if now >= playback.frame_time(playback.scheduled_frame + 1) {
+                    dropped_frames += 1;
+                    playback.scheduled_frame += 1;
+                    continue;
                 }
-            }
-        } else if path.is_file() && path.extension().is_some_and(|ext| ext == "txt") {
-            if let Some(file_stem) = path.file_stem().and_then(|s| s.to_str()) {
-                if let Ok(frame_num) = file_stem.parse::<u64>() {
-                    // Collect root files into a single SecondInfo { number: 0 } entry
-                    // Find or create the SecondInfo for number 0
-                    let second_0 = seconds.iter_mut().find(|s| s.number == 0);
-                    if let Some(second) = second_0 {
-                        second.frames.push(FrameInfo {
-                            path,
-                            number: frame_num,
-                        });
-                    } else {
-                        seconds.push(SecondInfo {
-                            number: 0,
-                            frames: vec![FrameInfo {
-                                path,
-                                number: frame_num,
-                            }],
-                        });
+
+                // Keep reacting to the keyboard while waiting for the frame to become due
+                if now < due {
+                    playback.pending = Some(frame);
+                    if let Some(action) = poll_action(due - now).map_err(Error::terminal)?
+                        && playback.apply(action)?
+                    {
+                        break;
                     }
-                } else {
-                    eprintln!(
-                        "Warning: Could not parse frame number from root file name: {path:?}"
-                    );
+                    continue;
                 }
             }
+
+            playback.step_requested = false;
+            playback.scheduled_frame += 1;
+            let overlay = self.info.then(|| {
+                let mut overlay = format!(
+                    " frame {}/{} | {} fps | x{} | dropped {dropped_frames} ",
+                    frame.index + 1,
+                    playback.frame_count,
+                    fps,
+                    playback.clock.speed()
+                );
+                if playback.clock.is_paused() {
+                    overlay.push_str("| paused ");
+                }
+                if let Some(charset) = &charset {
+                    overlay.push_str(&format!("| charset \"{charset}\" "));
+                }
+                overlay
+            });
+            playback
+                .screen
+                .draw(&frame.content, overlay.as_deref())
+                .map_err(Error::terminal)?;
         }
+
+        sink.stop();
+        Ok(dropped_frames)
     }
+}
 
-    // Sort frames within the root (second 0) if it exists
-    if let Some(second_0) = seconds.iter_mut().find(|s| s.number == 0) {
-        second_0.frames.sort_by_key(|f| f.number);
+/// Rendered frames opened by [`Player::frames`], in playback order
+pub struct Frames {
+    loader: FrameLoader,
+    fps: f64,
+}
+
+impl Frames {
+    /// Rate the frames are meant to be shown at
+    pub fn fps(&self) -> f64 {
+        self.fps
     }
 
-    seconds.sort_by_key(|s| s.number);
+    /// Number of frames in one pass; estimated from the duration for videos, 0 if unknown
+    pub fn len(&self) -> usize {
+        self.loader.len()
+    }
 
-    let ordered_frame_paths: Vec<PathBuf> = seconds
-        .into_iter()
-        .flat_map(|s| s.frames.into_iter().map(|f| f.path))
-        .collect();
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Continues from the frame at `index`; ignored when [`Frames::can_seek`] is `false`
+    pub fn seek(&mut self, index: usize) {
+        self.loader.seek(index);
+    }
 
-    Ok(ordered_frame_paths)
+    /// Whether the source can seek, which e.g. a video piped to standard input can't
+    pub fn can_seek(&self) -> bool {
+        self.loader.can_seek()
+    }
+}
+
+impl Iterator for Frames {
+    type Item = crate::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.loader
+            .next_frame()
+            .map(|frame| frame.map_err(Error::input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::consts::MANIFEST_FILE;
+    use std::fs;
+
+    #[test]
+    fn given_rate_and_audio_override_the_manifest() {
+        let dir = std::env::temp_dir().join(format!("ascii4-{}-play-open", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("1.txt"), "ab\ncd").unwrap();
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            r#"{"version":"0.3.0","source":"clip.mp4","duration":null,"fps":12.5,"width":2,
+                "height":2,"charset":" .:#","color":"none","frame_count":1,"audio":"clip.ogg"}"#,
+        )
+        .unwrap();
+
+        let recorded = Player::new(&dir).open();
+        let given = Player::new(&dir).fps(24.0).audio("other.ogg").open();
+        let _ = fs::remove_dir_all(&dir);
+
+        let recorded = recorded.unwrap();
+        assert_eq!(recorded.fps, 12.5);
+        assert_eq!(recorded.audio, Some(dir.join("clip.ogg")));
+        assert_eq!(recorded.charset.as_deref(), Some(" .:#"));
+
+        let given = given.unwrap();
+        assert_eq!(given.fps, 24.0);
+        assert_eq!(given.audio, Some(PathBuf::from("other.ogg")));
+    }
 }
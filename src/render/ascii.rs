@@ -0,0 +1,63 @@
+use super::{CellWriter, Renderer, dither};
+use crate::color_mode::ColorMode;
+use crate::dither::Dither;
+use image::{Rgb, RgbImage};
+
+/// Relative luminance of a pixel in the 0.0..=1.0 range (Rec. 601 weights)
+pub fn luminance(pixel: &Rgb<u8>) -> f32 {
+    let [r, g, b] = pixel.0;
+    (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0
+}
+
+/// Density ramp renderer: one character per pixel, optionally colored with the pixel color
+pub struct AsciiRenderer {
+    char_set: Vec<char>,
+    color: ColorMode,
+    dither: Dither,
+}
+
+impl AsciiRenderer {
+    pub fn new(char_set: Vec<char>, color: ColorMode, dither: Dither) -> Self {
+        Self {
+            char_set,
+            color,
+            dither,
+        }
+    }
+}
+
+impl Renderer for AsciiRenderer {
+    fn cell_size(&self) -> (u32, u32) {
+        (1, 1)
+    }
+
+    fn render(&self, img: &RgbImage) -> String {
+        let (width, height) = img.dimensions();
+        let mut writer = CellWriter::with_capacity((width as usize + 1) * height as usize);
+
+        // Glyphs pick from the dark-to-light ramp by luminance
+        let lumas: Vec<f32> = img.pixels().map(luminance).collect();
+        let glyphs = dither::levels(self.dither, width as usize, &lumas, self.char_set.len());
+        let colors = dither::colors(self.dither, img, self.color);
+
+        let row_length = (width as usize).max(1);
+        for (y, (glyphs, colors)) in glyphs
+            .chunks(row_length)
+            .zip(colors.chunks(row_length))
+            .enumerate()
+        {
+            if y > 0 {
+                writer.new_line();
+            }
+            for (&glyph, &color) in glyphs.iter().zip(colors) {
+                writer.push(
+                    self.char_set.get(glyph).copied().unwrap_or(' '),
+                    color,
+                    None,
+                );
+            }
+        }
+
+        writer.finish()
+    }
+}
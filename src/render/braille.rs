@@ -0,0 +1,109 @@
+use super::ascii::luminance;
+use super::{CellWriter, Renderer, dither};
+use crate::color_mode::ColorMode;
+use crate::dither::Dither;
+use image::{Rgb, RgbImage};
+
+/// First code point of the Unicode braille patterns block
+const BRAILLE_BASE: u32 = 0x2800;
+
+/// Dot bit for each pixel of a 2x4 cell, indexed as `[row][column]`
+const DOT_BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Braille renderer: every cell packs a 2x4 block of pixels into one dot pattern
+///
+/// A dot is raised when its pixel is brighter than the frame's mean luminance, which keeps
+/// detail visible in both dark and bright scenes. Dithering halftones the frame instead, so
+/// that the share of raised dots follows the brightness. With color, the cell takes the
+/// average color of its raised dots.
+pub struct BrailleRenderer {
+    color: ColorMode,
+    dither: Dither,
+}
+
+impl BrailleRenderer {
+    pub fn new(color: ColorMode, dither: Dither) -> Self {
+        Self { color, dither }
+    }
+}
+
+impl Renderer for BrailleRenderer {
+    fn cell_size(&self) -> (u32, u32) {
+        (2, 4)
+    }
+
+    fn render(&self, img: &RgbImage) -> String {
+        let (width, height) = img.dimensions();
+        let (columns, rows) = (width / 2, height / 4);
+        let mut writer = CellWriter::with_capacity((columns as usize * 4 + 1) * rows as usize);
+
+        let lumas: Vec<f32> = img.pixels().map(luminance).collect();
+        let raised: Vec<bool> = match self.dither {
+            Dither::None => {
+                let pixel_count = (width as f32 * height as f32).max(1.0);
+                let threshold = lumas.iter().sum::<f32>() / pixel_count;
+                lumas.iter().map(|&luma| luma > threshold).collect()
+            }
+            dither => dither::levels(dither, width as usize, &lumas, 2)
+                .into_iter()
+                .map(|level| level > 0)
+                .collect(),
+        };
+
+        let mut patterns = Vec::with_capacity(columns as usize * rows as usize);
+        // Average color of the raised dots of every cell, or of the whole cell if none is
+        let mut cell_colors = RgbImage::new(columns, rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let mut bits = 0;
+                let mut sum = [0u32; 3];
+                let mut all = [0u32; 3];
+                let mut count = 0;
+
+                for (dy, row_bits) in DOT_BITS.iter().enumerate() {
+                    for (dx, bit) in row_bits.iter().enumerate() {
+                        let (x, y) = (column * 2 + dx as u32, row * 4 + dy as u32);
+                        let pixel = img.get_pixel(x, y);
+                        for (total, channel) in all.iter_mut().zip(pixel.0) {
+                            *total += channel as u32;
+                        }
+                        if raised[(y * width + x) as usize] {
+                            bits |= bit;
+                            count += 1;
+                            for (total, channel) in sum.iter_mut().zip(pixel.0) {
+                                *total += channel as u32;
+                            }
+                        }
+                    }
+                }
+
+                let average = if count > 0 {
+                    sum.map(|total| (total / count) as u8)
+                } else {
+                    all.map(|total| (total / 8) as u8)
+                };
+                cell_colors.put_pixel(column, row, Rgb(average));
+                patterns.push(bits);
+            }
+        }
+
+        let colors = dither::colors(self.dither, &cell_colors, self.color);
+        let row_length = (columns as usize).max(1);
+        for (row, (patterns, colors)) in patterns
+            .chunks(row_length)
+            .zip(colors.chunks(row_length))
+            .enumerate()
+        {
+            if row > 0 {
+                writer.new_line();
+            }
+            for (&bits, &color) in patterns.iter().zip(colors) {
+                let glyph = char::from_u32(BRAILLE_BASE + bits).unwrap_or(' ');
+                let fg = if bits > 0 { color } else { None };
+                writer.push(glyph, fg, None);
+            }
+        }
+
+        writer.finish()
+    }
+}
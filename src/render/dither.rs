@@ -0,0 +1,247 @@
+use crate::color::{TermColor, palette_step};
+use crate::color_mode::ColorMode;
+use crate::dither::Dither;
+use image::{Rgb, RgbImage};
+
+/// Errors below this fraction of the gap between levels are dropped rather than diffused, so
+/// compression noise in flat areas doesn't reshuffle their pattern from one frame to the next
+const ERROR_DEADBAND: f32 = 0.15;
+
+/// Share of the quantization error passed on, so that a change in one part of the frame fades
+/// out instead of rippling through everything below and to the right of it
+const ERROR_DAMPING: f32 = 0.85;
+
+/// Error diffusion weights as (dx, dy, weight)
+type Kernel = [(i32, usize, f32)];
+
+const FLOYD_STEINBERG: [(i32, usize, f32); 4] = [
+    (1, 0, 7.0 / 16.0),
+    (-1, 1, 3.0 / 16.0),
+    (0, 1, 5.0 / 16.0),
+    (1, 1, 1.0 / 16.0),
+];
+
+/// Only spreads 6/8 of the error, which keeps highlights and shadows clean
+const ATKINSON: [(i32, usize, f32); 6] = [
+    (1, 0, 1.0 / 8.0),
+    (2, 0, 1.0 / 8.0),
+    (-1, 1, 1.0 / 8.0),
+    (0, 1, 1.0 / 8.0),
+    (1, 1, 1.0 / 8.0),
+    (0, 2, 1.0 / 8.0),
+];
+
+const BAYER_4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+const BAYER_8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Offset of the ordered dithering threshold at a position, in `-0.5..0.5` level gaps
+fn bayer_offset<const N: usize>(matrix: &[[u8; N]; N], x: usize, y: usize) -> f32 {
+    (matrix[y % N][x % N] as f32 + 0.5) / (N * N) as f32 - 0.5
+}
+
+/// Value being quantized: a luminance or the channels of a color
+trait Sample: Copy + Default {
+    fn offset(self, amount: f32) -> Self;
+    fn add_scaled(self, other: Self, factor: f32) -> Self;
+    fn sub(self, other: Self) -> Self;
+    fn clamp(self, max: f32) -> Self;
+    /// Largest absolute component
+    fn magnitude(self) -> f32;
+}
+
+impl Sample for f32 {
+    fn offset(self, amount: f32) -> Self {
+        self + amount
+    }
+
+    fn add_scaled(self, other: Self, factor: f32) -> Self {
+        self + other * factor
+    }
+
+    fn sub(self, other: Self) -> Self {
+        self - other
+    }
+
+    fn clamp(self, max: f32) -> Self {
+        f32::clamp(self, 0.0, max)
+    }
+
+    fn magnitude(self) -> f32 {
+        self.abs()
+    }
+}
+
+impl Sample for [f32; 3] {
+    fn offset(self, amount: f32) -> Self {
+        self.map(|channel| channel + amount)
+    }
+
+    fn add_scaled(self, other: Self, factor: f32) -> Self {
+        [0, 1, 2].map(|i| self[i] + other[i] * factor)
+    }
+
+    fn sub(self, other: Self) -> Self {
+        [0, 1, 2].map(|i| self[i] - other[i])
+    }
+
+    fn clamp(self, max: f32) -> Self {
+        self.map(|channel| channel.clamp(0.0, max))
+    }
+
+    fn magnitude(self) -> f32 {
+        self.iter()
+            .fold(0.0, |largest, channel| largest.max(channel.abs()))
+    }
+}
+
+/// Quantizes a row-major grid of `width` samples in `0.0..=max` with `dither`
+///
+/// `nearest` returns the level closest to a sample together with the value that level stands
+/// for, and `step` is the usual gap between neighbouring levels. Every frame is dithered on its
+/// own, so the outcome never depends on the frames before it: ordered patterns are fixed to
+/// screen positions, and error diffusion drops small and far travelling errors to keep
+/// unchanged areas from flickering.
+fn quantize<S: Sample, L>(
+    dither: Dither,
+    width: usize,
+    samples: &[S],
+    max: f32,
+    step: f32,
+    nearest: impl Fn(S) -> (L, S),
+) -> Vec<L> {
+    let width = width.max(1);
+    let ordered = |offset: fn(usize, usize) -> f32| {
+        samples
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                let offset = offset(i % width, i / width) * step;
+                nearest(sample.offset(offset).clamp(max)).0
+            })
+            .collect()
+    };
+    let kernel: &Kernel = match dither {
+        Dither::None => return samples.iter().map(|&sample| nearest(sample).0).collect(),
+        Dither::Bayer4 => return ordered(|x, y| bayer_offset(&BAYER_4, x, y)),
+        Dither::Bayer8 => return ordered(|x, y| bayer_offset(&BAYER_8, x, y)),
+        Dither::FloydSteinberg => &FLOYD_STEINBERG,
+        Dither::Atkinson => &ATKINSON,
+    };
+
+    let height = samples.len() / width;
+    let mut errors = vec![S::default(); samples.len()];
+    let mut levels = Vec::with_capacity(samples.len());
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let target = samples[index].add_scaled(errors[index], 1.0).clamp(max);
+            let (level, value) = nearest(target);
+            levels.push(level);
+
+            let error = target.sub(value);
+            if error.magnitude() < ERROR_DEADBAND * step {
+                continue;
+            }
+            for &(dx, dy, weight) in kernel {
+                let nx = x as i32 + dx;
+                if nx < 0 || nx >= width as i32 || y + dy >= height {
+                    continue;
+                }
+                let neighbour = (y + dy) * width + nx as usize;
+                errors[neighbour] = errors[neighbour].add_scaled(error, weight * ERROR_DAMPING);
+            }
+        }
+    }
+    levels
+}
+
+/// Indices into `count` evenly spaced levels over `0.0..=1.0` for a row-major grid of `width`
+/// luminance values
+pub fn levels(dither: Dither, width: usize, values: &[f32], count: usize) -> Vec<usize> {
+    // A single level leaves nothing to choose between
+    if count <= 1 {
+        return vec![0; values.len()];
+    }
+    let last = (count - 1) as f32;
+    quantize(dither, width, values, 1.0, 1.0 / last, |value| {
+        let level = (value.clamp(0.0, 1.0) * last).round();
+        (level as usize, level / last)
+    })
+}
+
+/// Terminal color of every pixel of `img` in `mode`, dithered across the palette
+pub fn colors(dither: Dither, img: &RgbImage, mode: ColorMode) -> Vec<Option<TermColor>> {
+    let Some(step) = palette_step(mode) else {
+        return img
+            .pixels()
+            .map(|pixel| TermColor::from_pixel(pixel, mode))
+            .collect();
+    };
+
+    let samples: Vec<[f32; 3]> = img.pixels().map(|pixel| pixel.0.map(f32::from)).collect();
+    quantize(
+        dither,
+        img.width() as usize,
+        &samples,
+        255.0,
+        step,
+        |sample| {
+            let color =
+                TermColor::from_pixel(&Rgb(sample.map(|channel| channel.round() as u8)), mode);
+            (
+                color,
+                color.map_or(sample, |color| color.rgb().map(f32::from)),
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Dither; 5] = [
+        Dither::None,
+        Dither::FloydSteinberg,
+        Dither::Atkinson,
+        Dither::Bayer4,
+        Dither::Bayer8,
+    ];
+
+    #[test]
+    fn single_level_ramps_always_pick_it() {
+        let values = [0.0, 0.4, 1.0, 0.9];
+        for dither in ALL {
+            assert_eq!(levels(dither, 2, &values, 1), vec![0; 4]);
+        }
+    }
+
+    #[test]
+    fn levels_stay_within_the_ramp() {
+        let values: Vec<f32> = (0..64).map(|n| n as f32 / 63.0).collect();
+        for dither in ALL {
+            assert!(levels(dither, 8, &values, 5).iter().all(|&level| level < 5));
+        }
+    }
+
+    #[test]
+    fn dithering_keeps_the_average_brightness() {
+        let values = vec![0.3; 256];
+        // Atkinson drops a quarter of the error on purpose, so it isn't expected to
+        for dither in [Dither::FloydSteinberg, Dither::Bayer4, Dither::Bayer8] {
+            let levels = levels(dither, 16, &values, 2);
+            let average = levels.iter().sum::<usize>() as f32 / levels.len() as f32;
+            assert!((average - 0.3).abs() < 0.1, "{dither:?} averaged {average}");
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use super::ascii::luminance;
+use super::{CellWriter, Renderer, dither};
+use crate::color_mode::ColorMode;
+use crate::dither::Dither;
+use image::RgbImage;
+
+/// Sobel gradient magnitude a pixel needs to start an edge, for luminance in 0.0..=1.0
+///
+/// A hard step from black to white measures 4.0.
+const STRONG_EDGE: f32 = 1.0;
+
+/// Magnitude down to which an edge started by a strong pixel is followed
+const WEAK_EDGE: f32 = 0.5;
+
+/// Direction an edge runs through a pixel, in cell units
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Orientation {
+    Horizontal,
+    /// Bottom left to top right
+    Rising,
+    Vertical,
+    /// Top left to bottom right
+    Falling,
+}
+
+impl Orientation {
+    /// Orientation of the edge across a gradient, with y pointing down as in the image
+    fn across(gx: f32, gy: f32) -> Self {
+        let angle = (-gx).atan2(-gy).to_degrees().rem_euclid(180.0);
+        match angle {
+            a if !(22.5..157.5).contains(&a) => Self::Horizontal,
+            a if a < 67.5 => Self::Rising,
+            a if a < 112.5 => Self::Vertical,
+            _ => Self::Falling,
+        }
+    }
+
+    /// Offsets of the two neighbours along the gradient, i.e. on either side of the edge
+    fn sides(self) -> [(i32, i32); 2] {
+        match self {
+            Self::Horizontal => [(0, -1), (0, 1)],
+            Self::Rising => [(-1, -1), (1, 1)],
+            Self::Vertical => [(-1, 0), (1, 0)],
+            Self::Falling => [(1, -1), (-1, 1)],
+        }
+    }
+}
+
+/// Luminance grid with clamped access past its borders
+struct Grid<'a> {
+    values: &'a [f32],
+    width: usize,
+    height: usize,
+}
+
+impl Grid<'_> {
+    fn at(&self, x: usize, y: usize, dx: i32, dy: i32) -> f32 {
+        let x = (x as i32 + dx).clamp(0, self.width as i32 - 1) as usize;
+        let y = (y as i32 + dy).clamp(0, self.height as i32 - 1) as usize;
+        self.values[y * self.width + x]
+    }
+
+    /// Sobel gradient at a pixel
+    fn gradient(&self, x: usize, y: usize) -> (f32, f32) {
+        let p = |dx, dy| self.at(x, y, dx, dy);
+        let gx = p(1, -1) + 2.0 * p(1, 0) + p(1, 1) - p(-1, -1) - 2.0 * p(-1, 0) - p(-1, 1);
+        let gy = p(-1, 1) + 2.0 * p(0, 1) + p(1, 1) - p(-1, -1) - 2.0 * p(0, -1) - p(1, -1);
+        (gx, gy)
+    }
+}
+
+/// Line-art renderer: Canny edges drawn with directional glyphs over the density ramp
+///
+/// Edges are found with a Sobel pass, thinned to one pixel by non-maximum suppression and
+/// traced from strong to weaker gradients, then drawn as `-`, `/`, `|` or `\` by their
+/// direction, or `_` for horizontal edges that lie on the lower side of the cell. Everything
+/// else gets the ramp glyph for its brightness, as in the plain ASCII mode.
+pub struct EdgeRenderer {
+    char_set: Vec<char>,
+    color: ColorMode,
+    dither: Dither,
+}
+
+impl EdgeRenderer {
+    pub fn new(char_set: Vec<char>, color: ColorMode, dither: Dither) -> Self {
+        Self {
+            char_set,
+            color,
+            dither,
+        }
+    }
+
+    /// Line glyph of every pixel on an edge, `None` elsewhere
+    fn edge_glyphs(lumas: &[f32], width: usize, height: usize) -> Vec<Option<char>> {
+        let grid = Grid {
+            values: lumas,
+            width,
+            height,
+        };
+        let gradients: Vec<(f32, f32)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| grid.gradient(x, y))
+            .collect();
+        let magnitudes: Vec<f32> = gradients.iter().map(|(gx, gy)| gx.hypot(*gy)).collect();
+        let magnitude = Grid {
+            values: &magnitudes,
+            width,
+            height,
+        };
+
+        // Non-maximum suppression keeps only the ridge of every edge; a step between two pixels
+        // is as steep on both of them, so ties go to the second one
+        let mut orientations = Vec::with_capacity(lumas.len());
+        let mut ridges = Vec::with_capacity(lumas.len());
+        for (index, &(gx, gy)) in gradients.iter().enumerate() {
+            let (x, y) = (index % width, index / width);
+            let orientation = Orientation::across(gx, gy);
+            let value = magnitudes[index];
+            let [(before_x, before_y), (after_x, after_y)] = orientation.sides();
+            let is_ridge = magnitude.at(x, y, before_x, before_y) < value
+                && magnitude.at(x, y, after_x, after_y) <= value;
+            orientations.push(orientation);
+            ridges.push(if is_ridge { value } else { 0.0 });
+        }
+
+        // Hysteresis: follow weak ridges only where they connect to a strong one
+        let mut on_edge = vec![false; lumas.len()];
+        let mut pending: Vec<usize> = (0..lumas.len())
+            .filter(|&index| ridges[index] >= STRONG_EDGE)
+            .collect();
+        for &index in &pending {
+            on_edge[index] = true;
+        }
+        while let Some(index) = pending.pop() {
+            let (x, y) = (index % width, index / width);
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                        continue;
+                    }
+                    let neighbour = ny as usize * width + nx as usize;
+                    if !on_edge[neighbour] && ridges[neighbour] >= WEAK_EDGE {
+                        on_edge[neighbour] = true;
+                        pending.push(neighbour);
+                    }
+                }
+            }
+        }
+
+        (0..lumas.len())
+            .map(|index| {
+                if !on_edge[index] {
+                    return None;
+                }
+                let (x, y) = (index % width, index / width);
+                Some(match orientations[index] {
+                    Orientation::Horizontal
+                        if magnitude.at(x, y, 0, 1) > magnitude.at(x, y, 0, -1) =>
+                    {
+                        '_'
+                    }
+                    Orientation::Horizontal => '-',
+                    Orientation::Rising => '/',
+                    Orientation::Vertical => '|',
+                    Orientation::Falling => '\\',
+                })
+            })
+            .collect()
+    }
+}
+
+impl Renderer for EdgeRenderer {
+    fn cell_size(&self) -> (u32, u32) {
+        (1, 1)
+    }
+
+    fn render(&self, img: &RgbImage) -> String {
+        let (width, height) = img.dimensions();
+        let mut writer = CellWriter::with_capacity((width as usize + 1) * height as usize);
+        if width == 0 || height == 0 {
+            return writer.finish();
+        }
+
+        let lumas: Vec<f32> = img.pixels().map(luminance).collect();
+        let edges = Self::edge_glyphs(&lumas, width as usize, height as usize);
+        let glyphs = dither::levels(self.dither, width as usize, &lumas, self.char_set.len());
+        let colors = dither::colors(self.dither, img, self.color);
+
+        let row_length = width as usize;
+        for (y, ((edges, glyphs), colors)) in edges
+            .chunks(row_length)
+            .zip(glyphs.chunks(row_length))
+            .zip(colors.chunks(row_length))
+            .enumerate()
+        {
+            if y > 0 {
+                writer.new_line();
+            }
+            for ((&edge, &glyph), &color) in edges.iter().zip(glyphs).zip(colors) {
+                let glyph =
+                    edge.unwrap_or_else(|| self.char_set.get(glyph).copied().unwrap_or(' '));
+                writer.push(glyph, color, None);
+            }
+        }
+
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 8;
+
+    /// Edge glyphs of a `SIZE` square with the given luminance, one string per row with `.`
+    /// where there is no edge
+    fn edges(luma: impl Fn(usize, usize) -> f32) -> Vec<String> {
+        let lumas: Vec<f32> = (0..SIZE * SIZE).map(|i| luma(i % SIZE, i / SIZE)).collect();
+        EdgeRenderer::edge_glyphs(&lumas, SIZE, SIZE)
+            .chunks(SIZE)
+            .map(|row| row.iter().map(|glyph| glyph.unwrap_or('.')).collect())
+            .collect()
+    }
+
+    fn step(bright: bool, level: f32) -> f32 {
+        if bright { level } else { 0.0 }
+    }
+
+    #[test]
+    fn vertical_steps_are_drawn_with_bars() {
+        let glyphs = edges(|x, _| step(x >= 4, 1.0));
+        assert!(glyphs.iter().all(|row| row == "...|...."), "{glyphs:?}");
+    }
+
+    #[test]
+    fn horizontal_edges_are_drawn_with_dashes_or_underscores_below() {
+        let line = edges(|_, y| step(y == 4, 1.0));
+        assert_eq!(line[3], "--------");
+        assert_eq!(line[5], "--------");
+        assert!(
+            line.iter().filter(|row| row.contains('-')).count() == 2,
+            "{line:?}"
+        );
+
+        // The edge is at the bottom of the dark row above the step
+        let step = edges(|_, y| step(y >= 4, 1.0));
+        assert_eq!(step[3], "________");
+        assert!(
+            step.iter().filter(|row| *row != "........").count() == 1,
+            "{step:?}"
+        );
+    }
+
+    #[test]
+    fn diagonal_steps_are_drawn_with_slashes() {
+        let falling = edges(|x, y| step(x > y, 1.0));
+        assert_eq!(falling[3], "...\\\\...");
+        assert!(falling.concat().chars().all(|glyph| "\\.".contains(glyph)));
+
+        let rising = edges(|x, y| step(x + y > SIZE - 1, 1.0));
+        assert_eq!(rising[3], "....//..");
+        assert!(rising.concat().chars().all(|glyph| "/.".contains(glyph)));
+    }
+
+    #[test]
+    fn weak_edges_are_kept_only_where_they_touch_strong_ones() {
+        // A step from 0.6 to 0.8 gives a gradient of 0.8, between WEAK_EDGE and STRONG_EDGE
+        let weak = |x: usize| if x >= 4 { 0.8 } else { 0.6 };
+        let alone = edges(|x, _| weak(x));
+        assert!(alone.iter().all(|row| row == "........"), "{alone:?}");
+
+        // A dark notch at the top of the step gives it strong edges to hang off
+        let joined = edges(|x, y| if x == 3 && y < 2 { 0.0 } else { weak(x) });
+        assert_eq!(joined[0], "..|.|...");
+        assert!(
+            joined[3..].iter().all(|row| row == "...|...."),
+            "{joined:?}"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use super::ascii::luminance;
+use super::{CellWriter, Renderer, dither};
+use crate::color_mode::ColorMode;
+use crate::dither::Dither;
+use image::RgbImage;
+
+const UPPER_HALF: char = '▀';
+const LOWER_HALF: char = '▄';
+const FULL_BLOCK: char = '█';
+
+/// Half-block renderer: every cell shows two vertically stacked pixels
+///
+/// With color the upper pixel is drawn as the foreground of `▀` and the lower one as its
+/// background; without color each half is simply switched on or off by its brightness.
+pub struct HalfBlockRenderer {
+    color: ColorMode,
+    dither: Dither,
+}
+
+impl HalfBlockRenderer {
+    pub fn new(color: ColorMode, dither: Dither) -> Self {
+        Self { color, dither }
+    }
+}
+
+impl Renderer for HalfBlockRenderer {
+    fn cell_size(&self) -> (u32, u32) {
+        (1, 2)
+    }
+
+    fn render(&self, img: &RgbImage) -> String {
+        let (width, height) = img.dimensions();
+        let rows = height / 2;
+        let mut writer = CellWriter::with_capacity((width as usize * 16 + 1) * rows as usize);
+
+        let colors = dither::colors(self.dither, img, self.color);
+        let lit: Vec<bool> = if self.color == ColorMode::None {
+            let lumas: Vec<f32> = img.pixels().map(luminance).collect();
+            dither::levels(self.dither, width as usize, &lumas, 2)
+                .into_iter()
+                .map(|level| level > 0)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        for row in 0..rows {
+            if row > 0 {
+                writer.new_line();
+            }
+            for x in 0..width {
+                let top = (row * 2 * width + x) as usize;
+                let bottom = top + width as usize;
+
+                match (colors[top], colors[bottom]) {
+                    (Some(fg), Some(bg)) => writer.push(UPPER_HALF, Some(fg), Some(bg)),
+                    _ => {
+                        let glyph = match (lit[top], lit[bottom]) {
+                            (true, true) => FULL_BLOCK,
+                            (true, false) => UPPER_HALF,
+                            (false, true) => LOWER_HALF,
+                            (false, false) => ' ',
+                        };
+                        writer.push(glyph, None, None);
+                    }
+                }
+            }
+        }
+
+        writer.finish()
+    }
+}
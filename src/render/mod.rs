@@ -0,0 +1,142 @@
+use crate::color::{SGR_RESET, TermColor};
+use crate::render_mode::RenderMode;
+use crate::render_options::RenderOptions;
+use image::RgbImage;
+
+pub mod ascii;
+pub mod braille;
+pub mod dither;
+pub mod edges;
+pub mod half_block;
+
+/// Turns a scaled frame into printable text
+pub trait Renderer: Send + Sync {
+    /// Pixels covered by one character cell as (columns, rows)
+    fn cell_size(&self) -> (u32, u32);
+
+    /// Renders an image whose dimensions are a multiple of [`Renderer::cell_size`]
+    fn render(&self, img: &RgbImage) -> String;
+}
+
+/// Builds the renderer for the mode, ramp, color and dithering options of `render`
+pub fn renderer_for(render: &RenderOptions) -> Box<dyn Renderer> {
+    let char_set = render.ramp().ramp().to_vec();
+    let (color, dither) = (render.color, render.dither);
+    match render.mode {
+        RenderMode::Ascii => Box::new(ascii::AsciiRenderer::new(char_set, color, dither)),
+        RenderMode::HalfBlock => Box::new(half_block::HalfBlockRenderer::new(color, dither)),
+        RenderMode::Braille => Box::new(braille::BrailleRenderer::new(color, dither)),
+        RenderMode::Edges => Box::new(edges::EdgeRenderer::new(char_set, color, dither)),
+    }
+}
+
+/// Accumulates colored cells, only emitting escape sequences when colors change
+pub struct CellWriter {
+    out: String,
+    fg: Option<TermColor>,
+    bg: Option<TermColor>,
+}
+
+impl CellWriter {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            out: String::with_capacity(capacity),
+            fg: None,
+            bg: None,
+        }
+    }
+
+    /// Appends one cell with optional foreground and background colors
+    pub fn push(&mut self, glyph: char, fg: Option<TermColor>, bg: Option<TermColor>) {
+        // Dropping a color requires a full reset, after which the other one must be re-sent
+        if (self.fg.is_some() && fg.is_none()) || (self.bg.is_some() && bg.is_none()) {
+            self.out.push_str(SGR_RESET);
+            self.fg = None;
+            self.bg = None;
+        }
+        if fg != self.fg {
+            if let Some(fg) = fg {
+                fg.write_fg(&mut self.out);
+            }
+            self.fg = fg;
+        }
+        if bg != self.bg {
+            if let Some(bg) = bg {
+                bg.write_bg(&mut self.out);
+            }
+            self.bg = bg;
+        }
+        self.out.push(glyph);
+    }
+
+    /// Finishes the current line, resetting colors so they never bleed past it
+    pub fn end_line(&mut self) {
+        if self.fg.is_some() || self.bg.is_some() {
+            self.out.push_str(SGR_RESET);
+            self.fg = None;
+            self.bg = None;
+        }
+    }
+
+    /// Starts a new line, closing the previous one first
+    pub fn new_line(&mut self) {
+        self.end_line();
+        self.out.push('\n');
+    }
+
+    pub fn finish(mut self) -> String {
+        self.end_line();
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color_mode::ColorMode;
+    use crate::dither::Dither;
+    use image::Rgb;
+
+    const RED: Option<TermColor> = Some(TermColor::Basic(9));
+    const BLUE: Option<TermColor> = Some(TermColor::Basic(4));
+
+    #[test]
+    fn runs_of_one_color_share_a_single_sgr() {
+        let mut writer = CellWriter::with_capacity(0);
+        for glyph in "abc".chars() {
+            writer.push(glyph, RED, None);
+        }
+        writer.push('d', BLUE, None);
+        writer.push('e', BLUE, None);
+        assert_eq!(writer.finish(), "\x1b[91mabc\x1b[34mde\x1b[0m");
+    }
+
+    #[test]
+    fn dropping_a_color_resets_and_sends_the_other_again() {
+        let mut writer = CellWriter::with_capacity(0);
+        writer.push('a', RED, BLUE);
+        writer.push('b', RED, None);
+        writer.push('c', None, None);
+        assert_eq!(writer.finish(), "\x1b[91m\x1b[44ma\x1b[0m\x1b[91mb\x1b[0mc");
+    }
+
+    #[test]
+    fn colors_are_reset_at_the_end_of_every_line() {
+        let mut writer = CellWriter::with_capacity(0);
+        writer.push('a', RED, None);
+        writer.new_line();
+        writer.push('b', RED, None);
+        assert_eq!(writer.finish(), "\x1b[91ma\x1b[0m\n\x1b[91mb\x1b[0m");
+    }
+
+    #[test]
+    fn rendered_rows_only_change_color_where_the_palette_does() {
+        let mut img = RgbImage::from_pixel(4, 1, Rgb([250, 10, 10]));
+        img.put_pixel(3, 0, Rgb([10, 10, 240]));
+        let renderer = ascii::AsciiRenderer::new(vec!['#'], ColorMode::Ansi256, Dither::None);
+        assert_eq!(
+            renderer.render(&img),
+            "\x1b[38;5;196m###\x1b[38;5;21m#\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use crate::color::SGR_RESET;
+use anyhow::Result;
+use crossterm::{cursor, queue, terminal};
+use std::fmt::Write as _;
+use std::io::{Write, stdout};
+
+/// Reverse video, used to highlight the info overlay
+const OVERLAY_STYLE: Style = Style {
+    fg: None,
+    bg: None,
+    attributes: 1 << 7,
+};
+
+/// Color selected by an SGR sequence, as the parameters that select it again
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SgrColor {
+    /// One of the 16 basic colors, by its own code (`30`–`37`, `90`–`97` and so on)
+    Code(u8),
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl SgrColor {
+    /// Reads the color following a `38` or `48` parameter
+    fn extended<'a>(params: &mut impl Iterator<Item = &'a str>) -> Option<Self> {
+        let mut next = || params.next().and_then(|param| param.parse::<u8>().ok());
+        match next()? {
+            5 => Some(Self::Indexed(next()?)),
+            2 => Some(Self::Rgb(next()?, next()?, next()?)),
+            _ => None,
+        }
+    }
+
+    fn write(&self, out: &mut String, extended: u8) {
+        let _ = match *self {
+            Self::Code(code) => write!(out, "{code}"),
+            Self::Indexed(index) => write!(out, "{extended};5;{index}"),
+            Self::Rgb(r, g, b) => write!(out, "{extended};2;{r};{g};{b}"),
+        };
+    }
+}
+
+/// Graphic rendition in effect for a cell
+///
+/// Every SGR sequence replaces the part of the state it sets, so cells compare equal whenever
+/// they look the same, whatever sequences led up to them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Style {
+    fg: Option<SgrColor>,
+    bg: Option<SgrColor>,
+    /// Bit `n` set for every active attribute `n` from 1 (bold) to 9 (crossed out)
+    attributes: u16,
+}
+
+impl Style {
+    fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Updates the state with the parameters of an SGR sequence
+    fn apply(&mut self, params: &str) {
+        let mut params = params.split(';');
+        while let Some(param) = params.next() {
+            let Ok(code) = (if param.is_empty() {
+                Ok(0)
+            } else {
+                param.parse::<u8>()
+            }) else {
+                continue;
+            };
+            match code {
+                0 => *self = Self::default(),
+                1..=9 => self.attributes |= 1 << code,
+                // 22 ends both bold and faint, the others end the attribute ten below them
+                22 => self.attributes &= !(1 << 1 | 1 << 2),
+                21 | 23..=29 => self.attributes &= !(1 << (code - 20)),
+                30..=37 | 90..=97 => self.fg = Some(SgrColor::Code(code)),
+                40..=47 | 100..=107 => self.bg = Some(SgrColor::Code(code)),
+                38 => self.fg = SgrColor::extended(&mut params),
+                48 => self.bg = SgrColor::extended(&mut params),
+                39 => self.fg = None,
+                49 => self.bg = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Appends a single SGR sequence selecting this style from a reset state
+    fn write(&self, out: &mut Vec<u8>) -> Result<()> {
+        let mut params = String::new();
+        for attribute in (1..=9).filter(|n| self.attributes & (1 << n) != 0) {
+            let _ = write!(params, "{attribute};");
+        }
+        if let Some(fg) = &self.fg {
+            fg.write(&mut params, 38);
+            params.push(';');
+        }
+        if let Some(bg) = &self.bg {
+            bg.write(&mut params, 48);
+            params.push(';');
+        }
+        if let Some(params) = params.strip_suffix(';') {
+            write!(out, "\x1b[{params}m")?;
+        }
+        Ok(())
+    }
+}
+
+/// One character cell together with the style it is drawn in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Cell {
+    glyph: char,
+    style: Style,
+}
+
+impl Cell {
+    fn blank() -> Self {
+        Self {
+            glyph: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+type Grid = Vec<Vec<Cell>>;
+
+/// Splits a frame into rows of cells, tracking SGR sequences so each cell knows its style
+fn parse_frame(content: &str) -> Grid {
+    let mut grid = Vec::new();
+
+    for line in content.lines() {
+        let mut row = Vec::new();
+        let mut style = Style::default();
+        let mut chars = line.chars();
+
+        while let Some(ch) = chars.next() {
+            if ch != '\x1b' {
+                row.push(Cell { glyph: ch, style });
+                continue;
+            }
+
+            // Control sequence: ESC [ parameters final-byte
+            let mut sequence = String::from(ch);
+            for next in chars.by_ref() {
+                sequence.push(next);
+                if next != '[' && ('@'..='~').contains(&next) {
+                    break;
+                }
+            }
+            if let Some(params) = sequence
+                .strip_prefix("\x1b[")
+                .and_then(|rest| rest.strip_suffix('m'))
+            {
+                style.apply(params);
+            }
+        }
+        grid.push(row);
+    }
+
+    grid
+}
+
+/// Size of a rendered frame in cells, not counting escape sequences
+pub fn text_size(content: &str) -> (u32, u32) {
+    let grid = parse_frame(content);
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    (width as u32, grid.len() as u32)
+}
+
+/// Writes a run of cells, switching styles only where they change
+fn write_cells(out: &mut Vec<u8>, cells: &[Cell]) -> Result<()> {
+    let mut current = Style::default();
+    for cell in cells {
+        if cell.style != current {
+            if !current.is_plain() {
+                out.write_all(SGR_RESET.as_bytes())?;
+            }
+            cell.style.write(out)?;
+            current = cell.style;
+        }
+        write!(out, "{}", cell.glyph)?;
+    }
+    if !current.is_plain() {
+        out.write_all(SGR_RESET.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Fits a frame into a terminal of `columns` x `rows` cells, centering it when it is smaller
+/// and keeping its center when it has to be cropped
+///
+/// Returns the visible grid and whether anything was cropped.
+fn fit(grid: &Grid, (columns, rows): (usize, usize)) -> (Grid, bool) {
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    let height = grid.len();
+    let cropped = width > columns || height > rows;
+
+    let (skip_rows, skip_columns) = (
+        (height.saturating_sub(rows)) / 2,
+        (width.saturating_sub(columns)) / 2,
+    );
+    let (pad_rows, pad_columns) = (
+        (rows.saturating_sub(height)) / 2,
+        (columns.saturating_sub(width)) / 2,
+    );
+
+    let mut fitted = vec![Vec::new(); pad_rows];
+    for row in grid.iter().skip(skip_rows).take(rows) {
+        let mut cells = vec![Cell::blank(); pad_columns];
+        cells.extend(row.iter().skip(skip_columns).take(columns).cloned());
+        fitted.push(cells);
+    }
+
+    (fitted, cropped)
+}
+
+/// Writes highlighted text over a row of the grid, within the first `columns` cells
+fn highlight_row(grid: &mut Grid, row: usize, text: &str, columns: usize) {
+    if grid.len() <= row {
+        grid.resize(row + 1, Vec::new());
+    }
+    let cells = &mut grid[row];
+    for (column, glyph) in text.chars().take(columns).enumerate() {
+        let cell = Cell {
+            glyph,
+            style: OVERLAY_STYLE,
+        };
+        match cells.get_mut(column) {
+            Some(existing) => *existing = cell,
+            None => {
+                cells.resize(column, Cell::blank());
+                cells.push(cell);
+            }
+        }
+    }
+}
+
+/// Frame handed to [`Screen::draw`], kept to draw it again after a resize
+struct Frame {
+    grid: Grid,
+    overlay: Option<String>,
+    len: usize,
+}
+
+/// Terminal renderer that only redraws the cells that changed since the previous frame
+///
+/// Frames are centered in the terminal, or cropped around their center with a status line when
+/// the terminal is too small for them. A full redraw happens for the first frame, after the
+/// terminal is resized and whenever the changes would take more bytes than the whole frame.
+/// Output is wrapped in synchronized update sequences, which terminals without support simply
+/// ignore.
+#[derive(Default)]
+pub struct Screen {
+    frame: Option<Frame>,
+    previous: Option<Grid>,
+    terminal_size: Option<(u16, u16)>,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draws a frame with an optional single-line overlay on top of its first row
+    pub fn draw(&mut self, content: &str, overlay: Option<&str>) -> Result<()> {
+        self.frame = Some(Frame {
+            grid: parse_frame(content),
+            overlay: overlay.map(str::to_string),
+            len: content.len(),
+        });
+        self.redraw()
+    }
+
+    /// Draws the last frame again, e.g. to adapt it to a new terminal size
+    pub fn redraw(&mut self) -> Result<()> {
+        let Some(frame) = &self.frame else {
+            return Ok(());
+        };
+
+        let size = terminal::size().ok();
+        let resized = size != self.terminal_size;
+        self.terminal_size = size;
+
+        let (mut grid, cropped, columns, rows) = match size {
+            Some((columns, rows)) => {
+                let (columns, rows) = (columns as usize, rows as usize);
+                let (grid, cropped) = fit(&frame.grid, (columns, rows));
+                (grid, cropped, columns, rows)
+            }
+            None => (frame.grid.clone(), false, usize::MAX, 0),
+        };
+        if let Some(text) = &frame.overlay {
+            highlight_row(&mut grid, 0, text, columns);
+        }
+        if cropped && rows > 1 {
+            let width = frame.grid.iter().map(Vec::len).max().unwrap_or(0);
+            let status = format!(
+                " terminal too small: frames are {width}x{}, terminal is {columns}x{rows} ",
+                frame.grid.len()
+            );
+            highlight_row(&mut grid, rows - 1, &status, columns);
+        }
+
+        let mut out = Vec::with_capacity(frame.len);
+        queue!(out, terminal::BeginSynchronizedUpdate)?;
+
+        let diff = match (&self.previous, resized) {
+            (Some(previous), false) => Some(Self::diff(previous, &grid)?),
+            _ => None,
+        };
+        match diff {
+            Some(diff) if diff.len() <= frame.len => out.extend_from_slice(&diff),
+            _ => Self::full(&mut out, &grid)?,
+        }
+
+        queue!(out, terminal::EndSynchronizedUpdate)?;
+
+        let mut stdout = stdout();
+        stdout.write_all(&out)?;
+        stdout.flush()?;
+
+        self.previous = Some(grid);
+        Ok(())
+    }
+
+    fn full(out: &mut Vec<u8>, grid: &Grid) -> Result<()> {
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+        for (row, cells) in grid.iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            write_cells(out, cells)?;
+        }
+        Ok(())
+    }
+
+    /// Cursor moves and cells for every run that differs between two grids
+    fn diff(previous: &Grid, grid: &Grid) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let blank = Cell::blank();
+
+        for row in 0..previous.len().max(grid.len()) {
+            let old = previous.get(row).map_or(&[][..], Vec::as_slice);
+            let new = grid.get(row).map_or(&[][..], Vec::as_slice);
+            let width = old.len().max(new.len());
+
+            let mut column = 0;
+            while column < width {
+                let differs = |column: usize| old.get(column) != new.get(column);
+                if !differs(column) {
+                    column += 1;
+                    continue;
+                }
+
+                let start = column;
+                while column < width && differs(column) {
+                    column += 1;
+                }
+
+                // Cells that disappeared are blanked out
+                let run: Vec<Cell> = (start..column)
+                    .map(|column| new.get(column).unwrap_or(&blank).clone())
+                    .collect();
+                queue!(out, cursor::MoveTo(start as u16, row as u16))?;
+                write_cells(&mut out, &run)?;
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_colors_replace_earlier_ones() {
+        let grid = parse_frame("\x1b[38;5;1ma\x1b[38;5;2mb\x1b[0m\x1b[38;5;2mc");
+        assert_eq!(grid[0][1].style, grid[0][2].style);
+        assert_ne!(grid[0][0].style, grid[0][1].style);
+    }
+
+    #[test]
+    fn style_changes_are_written_as_single_sequences() {
+        let row: String = (0..200)
+            .map(|n| format!("\x1b[38;2;{n};0;0m\x1b[48;5;{}m#", n % 256))
+            .collect();
+        let grid = parse_frame(&row);
+
+        let mut out = Vec::new();
+        write_cells(&mut out, &grid[0]).unwrap();
+        assert!(out.len() < row.len() + 200 * SGR_RESET.len());
+        assert_eq!(parse_frame(&String::from_utf8(out).unwrap()), grid);
+    }
+
+    #[test]
+    fn attributes_are_switched_off_by_their_reset_codes() {
+        let grid = parse_frame("\x1b[1;7ma\x1b[22mb\x1b[27mc");
+        assert_eq!(grid[0][1].style.attributes, 1 << 7);
+        assert!(grid[0][2].style.is_plain());
+    }
+}
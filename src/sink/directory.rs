@@ -0,0 +1,141 @@
+use super::{FrameSink, SinkHeader};
+use crate::types::consts::CHARSET_FILE;
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// How frame files are named
+#[derive(Clone, Copy)]
+enum Naming {
+    /// `{second}/{n}.txt`, numbered from 1 within each second
+    PerSecond,
+    /// `{n}.txt` directly in the root, numbered from 1
+    Flat,
+}
+
+/// One text file per frame under a root directory, with the character ramp next to them
+pub struct DirectorySink {
+    root: PathBuf,
+    naming: Naming,
+    fps: f64,
+}
+
+impl DirectorySink {
+    /// `{second}/{n}.txt` files, numbered from 1 within each second
+    pub fn per_second(root: PathBuf) -> Self {
+        Self {
+            root,
+            naming: Naming::PerSecond,
+            fps: 0.0,
+        }
+    }
+
+    /// `{n}.txt` files directly in `root`, zero-padded and numbered from 1
+    pub fn flat(root: PathBuf) -> Self {
+        Self {
+            root,
+            naming: Naming::Flat,
+            fps: 0.0,
+        }
+    }
+
+    /// Location of an output slot in the per-second layout: its second and 1-based number in it
+    fn slot_position(slot: u64, fps: f64) -> (u64, u64) {
+        let second_of = |slot: u64| (slot as f64 / fps).floor() as u64;
+        let second = second_of(slot);
+
+        let mut first_slot = (second as f64 * fps).ceil() as u64;
+        while first_slot > 0 && second_of(first_slot - 1) == second {
+            first_slot -= 1;
+        }
+        while second_of(first_slot) < second {
+            first_slot += 1;
+        }
+
+        (second, slot - first_slot + 1)
+    }
+}
+
+impl FrameSink for DirectorySink {
+    fn start(&mut self, header: &SinkHeader) -> Result<()> {
+        let root = &self.root;
+        fs::create_dir_all(root)
+            .with_context(|| format!("Failed to create main output directory: {root:?}"))?;
+
+        let charset_path = root.join(CHARSET_FILE);
+        fs::write(&charset_path, &header.charset)
+            .with_context(|| format!("Failed to write charset file: {charset_path:?}"))?;
+
+        self.fps = header.fps;
+        Ok(())
+    }
+
+    fn write(&self, slot: u64, content: &str) -> Result<()> {
+        let output_filename = match self.naming {
+            Naming::PerSecond => {
+                let (second, number) = Self::slot_position(slot, self.fps);
+                let second_dir = self.root.join(second.to_string());
+                fs::create_dir_all(&second_dir).with_context(|| {
+                    format!("Failed to create directory for second {second}: {second_dir:?}")
+                })?;
+                second_dir.join(format!("{number}.txt"))
+            }
+            Naming::Flat => self.root.join(format!("{:06}.txt", slot + 1)),
+        };
+
+        let mut file = fs::File::create(&output_filename)
+            .with_context(|| format!("Failed to create output file {output_filename:?}"))?;
+        file.write_all(content.as_bytes())
+            .with_context(|| format!("Failed to write ASCII art to file: {output_filename:?}"))
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn location(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_numbered_from_one_within_each_second() {
+        for fps in [29.97, 23.976, 15.0, 0.5] {
+            let mut previous = (0, 0);
+            for slot in 0..2000 {
+                let (second, number) = DirectorySink::slot_position(slot, fps);
+                assert_eq!(second, (slot as f64 / fps).floor() as u64, "fps {fps}");
+                let expected = if slot > 0 && second == previous.0 {
+                    previous.1 + 1
+                } else {
+                    1
+                };
+                assert_eq!(number, expected, "fps {fps}, slot {slot}");
+                previous = (second, number);
+            }
+        }
+    }
+
+    #[test]
+    fn non_integer_rates_drop_a_slot_from_some_seconds() {
+        let per_second = |second: u64| {
+            (0..2000)
+                .map(|slot| DirectorySink::slot_position(slot, 29.97))
+                .filter(|&(s, _)| s == second)
+                .count()
+        };
+        assert_eq!(per_second(0), 30);
+        assert_eq!(per_second(1), 30);
+        assert_eq!(per_second(33), 29);
+        assert_eq!(DirectorySink::slot_position(30, 29.97), (1, 1));
+        assert_eq!(DirectorySink::slot_position(89, 29.97), (2, 30));
+        assert_eq!(DirectorySink::slot_position(90, 29.97), (3, 1));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::types::{
+    color_mode::ColorMode,
+    consts::{CAST_EXTENSION, PACK_EXTENSION},
+    output_format::OutputFormat,
+};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+pub mod directory;
+pub mod pack;
+pub mod stream;
+
+/// Description of the frames a sink is about to receive
+#[derive(Clone, Debug)]
+pub struct SinkHeader {
+    pub fps: f64,
+    /// Frame size in character cells
+    pub width: u32,
+    pub height: u32,
+    /// Character ramp from darkest to lightest
+    pub charset: String,
+    pub color: ColorMode,
+}
+
+/// Anything converted frames can be stored in or sent to
+///
+/// Conversion workers write frames concurrently and in no particular order, so sinks that
+/// need them in order have to hold back the ones that arrive early. No frame arrives more
+/// than two per worker ahead of the oldest one not written yet, and a failed write stops
+/// the conversion.
+pub trait FrameSink: Send + Sync {
+    /// Prepares the output for frames described by `header`, before any frame is written
+    fn start(&mut self, header: &SinkHeader) -> Result<()>;
+
+    /// Stores the frame of output slot `slot`, counted from 0
+    fn write(&self, slot: u64, content: &str) -> Result<()>;
+
+    /// Completes the output once every frame has been written
+    fn finish(&mut self) -> Result<()>;
+
+    /// Directory or file the manifest and soundtrack are kept with, if there is one
+    fn location(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Where `format` writes for `output`: files get the format's extension if `output` has none
+pub fn output_path(format: OutputFormat, output: &Path) -> PathBuf {
+    let extension = match format {
+        OutputFormat::Pack => PACK_EXTENSION,
+        OutputFormat::Cast => CAST_EXTENSION,
+        OutputFormat::Dirs | OutputFormat::Flat | OutputFormat::Stdout => {
+            return output.to_path_buf();
+        }
+    };
+    if output.extension().is_some() {
+        output.to_path_buf()
+    } else {
+        output.with_extension(extension)
+    }
+}
+
+/// Sink writing `format` to `output`; `compress` only applies to packs
+pub fn open_sink(format: OutputFormat, output: &Path, compress: bool) -> Box<dyn FrameSink> {
+    let path = output_path(format, output);
+    match format {
+        OutputFormat::Dirs => Box::new(directory::DirectorySink::per_second(path)),
+        OutputFormat::Flat => Box::new(directory::DirectorySink::flat(path)),
+        OutputFormat::Pack => Box::new(pack::PackSink::new(path, compress)),
+        OutputFormat::Stdout => Box::new(stream::StreamSink::stdout()),
+        OutputFormat::Cast => Box::new(stream::StreamSink::cast(path)),
+    }
+}
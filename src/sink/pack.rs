@@ -0,0 +1,73 @@
+use super::{FrameSink, SinkHeader};
+use crate::pack::{PackHeader, PackWriter, encode_frame};
+use anyhow::{Context, Result, anyhow};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Single packed container file
+pub struct PackSink {
+    path: PathBuf,
+    compressed: bool,
+    writer: Option<Mutex<PackWriter>>,
+}
+
+impl PackSink {
+    pub fn new(path: PathBuf, compressed: bool) -> Self {
+        Self {
+            path,
+            compressed,
+            writer: None,
+        }
+    }
+}
+
+impl FrameSink for PackSink {
+    fn start(&mut self, header: &SinkHeader) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create output directory: {parent:?}"))?;
+        }
+
+        let header = PackHeader {
+            fps: header.fps,
+            width: header.width,
+            height: header.height,
+            charset: header.charset.clone(),
+            color: header.color,
+            compressed: self.compressed,
+            frame_count: 0,
+        };
+        self.writer = Some(Mutex::new(PackWriter::create(&self.path, &header)?));
+        Ok(())
+    }
+
+    fn write(&self, slot: u64, content: &str) -> Result<()> {
+        let writer = self
+            .writer
+            .as_ref()
+            .ok_or_else(|| anyhow!("Container has not been created"))?;
+        // Compress before taking the lock so workers only serialize on the actual write
+        let payload = encode_frame(content, self.compressed)?;
+        writer
+            .lock()
+            .map_err(|_| anyhow!("Container writer is poisoned"))?
+            .write_frame(slot, payload)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer
+                .into_inner()
+                .map_err(|_| anyhow!("Container writer is poisoned"))?
+                .finish()?;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+}
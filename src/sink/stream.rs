@@ -0,0 +1,166 @@
+use super::{FrameSink, SinkHeader};
+use anyhow::{Context, Result, anyhow};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Separates frames streamed to standard output, so readers can split them apart
+const FRAME_SEPARATOR: &str = "\n\x0c\n";
+
+/// Clears the screen and homes the cursor before every frame of a recording
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// How frames are laid out in the stream
+enum Style {
+    /// Frame text followed by a form feed line
+    Plain,
+    /// asciicast v2 events timed at `fps` frames per second
+    Cast { path: PathBuf, fps: f64 },
+}
+
+struct StreamState {
+    out: Option<Box<dyn Write + Send>>,
+    /// Slot of the next frame to be written
+    next: u64,
+    /// Frames that arrived before the ones preceding them
+    pending: BTreeMap<u64, String>,
+}
+
+/// Frames written one after another to a single stream, in slot order
+pub struct StreamSink {
+    style: Style,
+    state: Mutex<StreamState>,
+}
+
+impl StreamSink {
+    fn new(style: Style) -> Self {
+        Self {
+            style,
+            state: Mutex::new(StreamState {
+                out: None,
+                next: 0,
+                pending: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Frames on standard output, separated by form feeds
+    pub fn stdout() -> Self {
+        Self::new(Style::Plain)
+    }
+
+    /// asciicast v2 recording at `path`
+    pub fn cast(path: PathBuf) -> Self {
+        Self::new(Style::Cast { path, fps: 0.0 })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, StreamState>> {
+        self.state
+            .lock()
+            .map_err(|_| anyhow!("Output stream is poisoned"))
+    }
+
+    fn write_frame(&self, out: &mut dyn Write, slot: u64, content: &str) -> Result<()> {
+        match &self.style {
+            Style::Plain => {
+                out.write_all(content.as_bytes())?;
+                out.write_all(FRAME_SEPARATOR.as_bytes())?;
+            }
+            Style::Cast { fps, .. } => {
+                let data = format!("{CLEAR_SCREEN}{}", content.replace('\n', "\r\n"));
+                writeln!(
+                    out,
+                    "[{:.6}, \"o\", {}]",
+                    slot as f64 / fps,
+                    serde_json::to_string(&data)?
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FrameSink for StreamSink {
+    fn start(&mut self, header: &SinkHeader) -> Result<()> {
+        let out: Box<dyn Write + Send> = match &mut self.style {
+            Style::Plain => Box::new(io::stdout()),
+            Style::Cast { path, fps } => {
+                *fps = header.fps;
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create output directory: {parent:?}")
+                    })?;
+                }
+                let mut file = BufWriter::new(
+                    fs::File::create(&*path)
+                        .with_context(|| format!("Failed to create recording: {path:?}"))?,
+                );
+                writeln!(
+                    file,
+                    "{{\"version\": 2, \"width\": {}, \"height\": {}}}",
+                    header.width, header.height
+                )?;
+                Box::new(file)
+            }
+        };
+
+        let state = self
+            .state
+            .get_mut()
+            .map_err(|_| anyhow!("Output stream is poisoned"))?;
+        state.out = Some(out);
+        Ok(())
+    }
+
+    fn write(&self, slot: u64, content: &str) -> Result<()> {
+        let mut state = self.lock()?;
+        let state = &mut *state;
+        let out = state
+            .out
+            .as_mut()
+            .ok_or_else(|| anyhow!("Output stream has not been opened"))?;
+
+        if slot != state.next {
+            state.pending.insert(slot, content.to_string());
+            return Ok(());
+        }
+        self.write_frame(out, slot, content)?;
+        state.next += 1;
+        // Frames leave the queue only once written, so a failed write loses nothing
+        while let Some(content) = state.pending.get(&state.next) {
+            self.write_frame(out, state.next, content)?;
+            state.pending.remove(&state.next);
+            state.next += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let state = self
+            .state
+            .get_mut()
+            .map_err(|_| anyhow!("Output stream is poisoned"))?;
+        if let Some(mut out) = state.out.take() {
+            out.flush().context("Failed to flush output stream")?;
+        }
+        if !state.pending.is_empty() {
+            return Err(anyhow!(
+                "Frame {} was never written, {} frames after it were dropped",
+                state.next + 1,
+                state.pending.len()
+            ));
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> Option<&Path> {
+        match &self.style {
+            Style::Plain => None,
+            Style::Cast { path, .. } => Some(path),
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use super::{FrameSource, Picture, SourceFrame, scale::Unscaled};
+use anyhow::{Context, Result, anyhow};
+use image::{
+    AnimationDecoder, Delay, Frame, Frames, ImageFormat,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+/// GIF delays up to this are played back at [`GIF_FALLBACK_DELAY_MS`], as browsers do, since
+/// such files were made for players that ignore tiny delays
+const GIF_MIN_DELAY_MS: f64 = 10.0;
+const GIF_FALLBACK_DELAY_MS: f64 = 100.0;
+
+/// Frames composited ahead of the one handed out
+const DECODE_AHEAD_FRAMES: usize = 2;
+
+/// Frames of `path` as the image crate composites them, or `None` if it isn't animated
+fn decode(path: &Path, format: ImageFormat) -> Result<Option<Frames<'static>>> {
+    let open = || -> Result<BufReader<File>> {
+        let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+        Ok(BufReader::new(file))
+    };
+
+    let frames = match format {
+        ImageFormat::Gif => GifDecoder::new(open()?)?.into_frames(),
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(open()?)?;
+            if !decoder.is_apng()? {
+                return Ok(None);
+            }
+            decoder.apng()?.into_frames()
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(open()?)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decoder.into_frames()
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(frames))
+}
+
+/// Start time of every frame with the given delays, and the length of the animation; only
+/// GIF delays are taken with a grain of salt
+fn start_times(delays: impl IntoIterator<Item = Delay>, is_gif: bool) -> (Vec<f64>, f64) {
+    let mut time = 0.0;
+    let starts = delays
+        .into_iter()
+        .map(|delay| {
+            let (numer, denom) = delay.numer_denom_ms();
+            let delay = numer as f64 / denom.max(1) as f64;
+            let delay = if is_gif && delay <= GIF_MIN_DELAY_MS {
+                GIF_FALLBACK_DELAY_MS
+            } else {
+                delay
+            };
+
+            let start = time;
+            time += delay / 1000.0;
+            start
+        })
+        .collect();
+    (starts, time)
+}
+
+/// Frames composited in order on a background thread
+///
+/// The decoder isn't `Send`, so it lives on the thread, which stops once the receiving side
+/// is dropped.
+struct Decoding {
+    frames: Receiver<Result<Frame>>,
+    /// Index of the frame received next
+    next: usize,
+}
+
+impl Decoding {
+    fn start(path: PathBuf, format: ImageFormat) -> Self {
+        let (frame_tx, frame_rx) = mpsc::sync_channel(DECODE_AHEAD_FRAMES);
+        thread::spawn(move || {
+            let frames = match decode(&path, format) {
+                Ok(Some(frames)) => frames,
+                Ok(None) => return,
+                Err(e) => {
+                    let _ = frame_tx.send(Err(e));
+                    return;
+                }
+            };
+            for frame in frames {
+                let frame = frame.with_context(|| format!("Failed to decode frame: {path:?}"));
+                if frame_tx.send(frame).is_err() {
+                    return;
+                }
+            }
+        });
+        Self {
+            frames: frame_rx,
+            next: 0,
+        }
+    }
+}
+
+/// Animated GIF, APNG or WebP whose frames are composited as they are needed
+///
+/// Opening goes through the frames once to learn their timing without keeping any; frames
+/// are then decoded again in order while they are read, starting over for a seek backwards.
+pub struct Animation {
+    path: PathBuf,
+    format: ImageFormat,
+    starts: Vec<f64>,
+    duration: f64,
+    size: (u32, u32),
+    /// Output size; frames keep their own size until one is set
+    scaled: Option<(u32, u32)>,
+    decoding: Option<Decoding>,
+    next: usize,
+}
+
+impl Animation {
+    /// Opens `path` if it is an animated image, returning `None` for anything else
+    pub fn open(path: &Path) -> Result<Option<Self>> {
+        let Ok(format) = ImageFormat::from_path(path) else {
+            return Ok(None);
+        };
+        let Some(frames) = decode(path, format)? else {
+            return Ok(None);
+        };
+
+        let mut size = (0, 0);
+        let delays = frames
+            .enumerate()
+            .map(|(index, frame)| {
+                let frame = frame?;
+                if index == 0 {
+                    size = frame.buffer().dimensions();
+                }
+                Ok(frame.delay())
+            })
+            .collect::<image::ImageResult<Vec<_>>>()
+            .with_context(|| format!("Failed to decode animation frames: {path:?}"))?;
+        let (starts, duration) = start_times(delays, format == ImageFormat::Gif);
+
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            format,
+            starts,
+            duration,
+            size,
+            scaled: None,
+            decoding: None,
+            next: 0,
+        }))
+    }
+
+    /// When frame `index` stops being shown
+    fn frame_end(&self, index: usize) -> f64 {
+        self.starts.get(index + 1).copied().unwrap_or(self.duration)
+    }
+
+    /// Composites frame `index`, decoding from the start again if it was passed already
+    fn decode_frame(&mut self, index: usize) -> Result<Frame> {
+        let decoding = match &mut self.decoding {
+            Some(decoding) if decoding.next <= index => decoding,
+            _ => self
+                .decoding
+                .insert(Decoding::start(self.path.clone(), self.format)),
+        };
+        loop {
+            let frame = decoding
+                .frames
+                .recv()
+                .map_err(|_| anyhow!("Animation ended early: {:?}", self.path))??;
+            decoding.next += 1;
+            if decoding.next > index {
+                return Ok(frame);
+            }
+        }
+    }
+}
+
+impl FrameSource for Animation {
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn duration(&self) -> Option<f64> {
+        Some(self.duration)
+    }
+
+    fn fps(&self) -> Option<f64> {
+        None
+    }
+
+    fn scale_to(&mut self, size: (u32, u32)) -> Result<()> {
+        self.scaled = Some(size);
+        Ok(())
+    }
+
+    fn next_frame(&mut self, time: f64) -> Result<Option<SourceFrame>> {
+        while self.next < self.starts.len() && self.frame_end(self.next) <= time {
+            self.next += 1;
+        }
+        if self.next >= self.starts.len() {
+            return Ok(None);
+        }
+
+        let buffer = self.decode_frame(self.next)?.into_buffer();
+        let frame = SourceFrame {
+            time: self.starts[self.next],
+            end: self.frame_end(self.next),
+            number: self.next as u64 + 1,
+            picture: Picture::Unscaled(Unscaled::rgba(buffer, self.scaled.unwrap_or(self.size))),
+        };
+        self.next += 1;
+        Ok(Some(frame))
+    }
+
+    fn seek(&mut self, time: f64) -> Result<()> {
+        self.next = self
+            .starts
+            .partition_point(|start| *start <= time)
+            .saturating_sub(1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage, codecs::gif::GifEncoder};
+    use std::fs;
+
+    /// Animated GIF in the temp directory, removed on drop
+    struct TempGif(PathBuf);
+
+    impl TempGif {
+        /// Gray frames of the given shades, 50 ms each
+        fn new(name: &str, shades: &[u8]) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("ascii4-{}-{name}.gif", std::process::id()));
+            let mut encoder = GifEncoder::new(File::create(&path).unwrap());
+            let frames = shades.iter().map(|&shade| {
+                let image = RgbaImage::from_pixel(4, 2, Rgba([shade, shade, shade, 255]));
+                Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(50, 1))
+            });
+            encoder.encode_frames(frames).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempGif {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn assert_times(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+        }
+    }
+
+    fn delays(milliseconds: &[u32]) -> Vec<Delay> {
+        milliseconds
+            .iter()
+            .map(|&ms| Delay::from_numer_denom_ms(ms, 1))
+            .collect()
+    }
+
+    /// Number of the next frame shown at `time` and the shade of its first pixel
+    fn shown_at(animation: &mut Animation, time: f64) -> (u64, u8) {
+        let frame = animation.next_frame(time).unwrap().unwrap();
+        let Picture::Unscaled(pixels) = frame.picture else {
+            panic!("animation frames are handed out unscaled");
+        };
+        let image = pixels.scale(&mut Default::default()).unwrap();
+        (frame.number, image.get_pixel(0, 0).0[0])
+    }
+
+    #[test]
+    fn tiny_gif_delays_fall_back_to_100_ms() {
+        let (starts, duration) = start_times(delays(&[10, 0, 20, 50]), true);
+        assert_times(&starts, &[0.0, 0.1, 0.2, 0.22]);
+        assert_times(&[duration], &[0.27]);
+    }
+
+    #[test]
+    fn other_formats_keep_their_delays() {
+        let (starts, duration) = start_times(delays(&[10, 0, 20]), false);
+        assert_times(&starts, &[0.0, 0.01, 0.01]);
+        assert_times(&[duration], &[0.03]);
+    }
+
+    #[test]
+    fn frames_are_decoded_again_after_seeking_back() {
+        let gif = TempGif::new("frames", &[0, 120, 240]);
+        let mut animation = Animation::open(&gif.0).unwrap().unwrap();
+        assert_eq!(animation.size(), (4, 2));
+        assert_times(&[animation.duration().unwrap()], &[0.15]);
+
+        assert_eq!(shown_at(&mut animation, 0.0), (1, 0));
+        assert_eq!(shown_at(&mut animation, 0.1), (3, 240));
+        animation.seek(0.06).unwrap();
+        assert_eq!(shown_at(&mut animation, 0.06), (2, 120));
+    }
+}
@@ -0,0 +1,280 @@
+use super::{FrameSource, Picture, SourceFrame};
+use crate::error::Warnings;
+use crate::manifest::Manifest;
+use crate::pack::PackReader;
+use crate::screen::text_size;
+use crate::types::{
+    consts::{CHARSET_FILE, DEFAULT_PLAY_FPS},
+    info::{FrameInfo, SecondInfo},
+};
+use anyhow::{Context, Result, anyhow};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Random-access source of rendered frames
+enum FrameStore {
+    /// Frame files in playback order
+    Files(Vec<PathBuf>),
+    /// Packed container
+    Pack(PackReader),
+}
+
+impl FrameStore {
+    fn len(&self) -> usize {
+        match self {
+            Self::Files(paths) => paths.len(),
+            Self::Pack(reader) => reader.header().frame_count as usize,
+        }
+    }
+
+    fn read(&mut self, index: usize) -> Result<String> {
+        match self {
+            Self::Files(paths) => {
+                let path = &paths[index];
+                fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read frame file: {path:?}"))
+            }
+            Self::Pack(reader) => reader.read_frame(index),
+        }
+    }
+}
+
+/// Frames rendered earlier by `convert`, in a frames directory or a packed container
+///
+/// Frames are shown at the rate recorded in the manifest or container unless another one is
+/// given, so a different rate plays them faster or slower rather than dropping any.
+pub struct TextFrames {
+    store: FrameStore,
+    path: PathBuf,
+    manifest: Option<Manifest>,
+    fps: f64,
+    size: (u32, u32),
+    charset: Option<String>,
+    next: usize,
+}
+
+impl TextFrames {
+    /// Indexes a per-second directory layout along with its character ramp
+    ///
+    /// Files and directories that don't fit the layout are skipped with a warning, as is an
+    /// unreadable manifest.
+    pub fn open_directory(
+        frames_dir: &Path,
+        fps: Option<f64>,
+        warnings: &Warnings,
+    ) -> Result<Self> {
+        let ordered_frames = discover_and_sort_frames(frames_dir, warnings)?;
+        if ordered_frames.is_empty() {
+            return Err(anyhow!(
+                "No valid frame files found in directory structure: {frames_dir:?}"
+            ));
+        }
+
+        let manifest = load_manifest(frames_dir, warnings);
+        // Written by `convert`; older outputs simply don't show a ramp in the overlay
+        let charset = fs::read_to_string(frames_dir.join(CHARSET_FILE))
+            .ok()
+            .or_else(|| Some(manifest.as_ref()?.charset.clone()));
+        let size =
+            match &manifest {
+                Some(manifest) => (manifest.width, manifest.height),
+                None => text_size(&fs::read_to_string(&ordered_frames[0]).with_context(|| {
+                    format!("Failed to read frame file: {:?}", ordered_frames[0])
+                })?),
+            };
+
+        Ok(Self {
+            fps: fps
+                .or(manifest.as_ref().map(|manifest| manifest.fps))
+                .unwrap_or(DEFAULT_PLAY_FPS),
+            store: FrameStore::Files(ordered_frames),
+            path: frames_dir.to_path_buf(),
+            manifest,
+            size,
+            charset,
+            next: 0,
+        })
+    }
+
+    /// Opens a packed container along with its character ramp, warning about an unreadable
+    /// manifest
+    pub fn open_pack(path: &Path, fps: Option<f64>, warnings: &Warnings) -> Result<Self> {
+        let reader = PackReader::open(path)?;
+        let header = reader.header();
+        if header.frame_count == 0 {
+            return Err(anyhow!("Container has no frames: {path:?}"));
+        }
+
+        Ok(Self {
+            fps: fps.unwrap_or(header.fps),
+            size: (header.width, header.height),
+            charset: Some(header.charset.clone()),
+            manifest: load_manifest(path, warnings),
+            path: path.to_path_buf(),
+            store: FrameStore::Pack(reader),
+            next: 0,
+        })
+    }
+}
+
+/// Settings recorded by `convert`, if it wrote a readable manifest
+fn load_manifest(frames: &Path, warnings: &Warnings) -> Option<Manifest> {
+    Manifest::load(frames).unwrap_or_else(|e| {
+        warnings.warn(format_args!("Ignoring manifest: {e:#}"));
+        None
+    })
+}
+
+impl FrameSource for TextFrames {
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn duration(&self) -> Option<f64> {
+        Some(self.store.len() as f64 / self.fps)
+    }
+
+    fn fps(&self) -> Option<f64> {
+        Some(self.fps)
+    }
+
+    fn is_text(&self) -> bool {
+        true
+    }
+
+    fn charset(&self) -> Option<String> {
+        self.charset.clone()
+    }
+
+    fn audio_path(&self) -> Option<PathBuf> {
+        self.manifest.as_ref()?.audio_path(&self.path)
+    }
+
+    fn scale_to(&mut self, _size: (u32, u32)) -> Result<()> {
+        Ok(())
+    }
+
+    fn next_frame(&mut self, time: f64) -> Result<Option<SourceFrame>> {
+        let end_of = |index: usize| (index + 1) as f64 / self.fps;
+        while self.next < self.store.len() && end_of(self.next) <= time {
+            self.next += 1;
+        }
+        if self.next >= self.store.len() {
+            return Ok(None);
+        }
+
+        let frame = SourceFrame {
+            time: self.next as f64 / self.fps,
+            end: end_of(self.next),
+            number: self.next as u64 + 1,
+            picture: Picture::Text(self.store.read(self.next)?),
+        };
+        self.next += 1;
+        Ok(Some(frame))
+    }
+
+    fn seek(&mut self, time: f64) -> Result<()> {
+        self.next = (time.max(0.0) * self.fps) as usize;
+        Ok(())
+    }
+}
+
+/// Discovers and sorts frame files in directory
+fn discover_and_sort_frames(base_dir: &Path, warnings: &Warnings) -> Result<Vec<PathBuf>> {
+    let mut seconds: Vec<SecondInfo> = Vec::new();
+
+    let entries = fs::read_dir(base_dir)
+        .with_context(|| format!("Failed to read base directory: {base_dir:?}"))?;
+
+    for entry_res in entries {
+        let entry = entry_res?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
+                if let Ok(second_num) = dir_name.parse::<u64>() {
+                    let mut current_second = SecondInfo {
+                        number: second_num,
+                        frames: Vec::new(),
+                    };
+
+                    for frame_entry_res in fs::read_dir(&path)
+                        .with_context(|| format!("Failed to read second directory: {path:?}"))?
+                    {
+                        let frame_entry = frame_entry_res?;
+                        let frame_path = frame_entry.path();
+
+                        if frame_path.is_file()
+                            && frame_path.extension().is_some_and(|ext| ext == "txt")
+                            && let Some(frame_stem) =
+                                frame_path.file_stem().and_then(|s| s.to_str())
+                        {
+                            if let Ok(frame_num) = frame_stem.parse::<u64>() {
+                                current_second.frames.push(FrameInfo {
+                                    path: frame_path,
+                                    number: frame_num,
+                                });
+                            } else {
+                                warnings.warn(format_args!(
+                                    "Could not parse frame number from file name: {frame_path:?}"
+                                ));
+                            }
+                        }
+                    }
+                    current_second.frames.sort_by_key(|f| f.number);
+
+                    if !current_second.frames.is_empty() {
+                        seconds.push(current_second);
+                    }
+                } else {
+                    warnings.warn(format_args!(
+                        "Directory name is not a valid second number: {path:?}"
+                    ));
+                }
+            }
+        } else if path.is_file()
+            && path.extension().is_some_and(|ext| ext == "txt")
+            && let Some(file_stem) = path.file_stem().and_then(|s| s.to_str())
+        {
+            if let Ok(frame_num) = file_stem.parse::<u64>() {
+                // Collect root files into a single SecondInfo { number: 0 } entry
+                // Find or create the SecondInfo for number 0
+                let second_0 = seconds.iter_mut().find(|s| s.number == 0);
+                if let Some(second) = second_0 {
+                    second.frames.push(FrameInfo {
+                        path,
+                        number: frame_num,
+                    });
+                } else {
+                    seconds.push(SecondInfo {
+                        number: 0,
+                        frames: vec![FrameInfo {
+                            path,
+                            number: frame_num,
+                        }],
+                    });
+                }
+            } else {
+                warnings.warn(format_args!(
+                    "Could not parse frame number from root file name: {path:?}"
+                ));
+            }
+        }
+    }
+
+    // Sort frames within the root (second 0) if it exists
+    if let Some(second_0) = seconds.iter_mut().find(|s| s.number == 0) {
+        second_0.frames.sort_by_key(|f| f.number);
+    }
+
+    seconds.sort_by_key(|s| s.number);
+
+    let ordered_frame_paths: Vec<PathBuf> = seconds
+        .into_iter()
+        .flat_map(|s| s.frames.into_iter().map(|f| f.path))
+        .collect();
+
+    Ok(ordered_frame_paths)
+}
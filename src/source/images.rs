@@ -0,0 +1,124 @@
+use super::{FrameSource, Picture, SourceFrame, scale::Unscaled};
+use crate::error::Warnings;
+use crate::types::consts::DEFAULT_PLAY_FPS;
+use anyhow::{Context, Result, anyhow};
+use image::ImageFormat;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Expands image inputs into a list of files: directories contribute the images directly
+/// inside them and patterns with `*`, `?` or `[` are matched as globs
+///
+/// Matches that can't be read are skipped with a warning.
+pub fn collect_images(inputs: &[String], warnings: &Warnings) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let mut images: Vec<PathBuf> = fs::read_dir(path)
+                .with_context(|| format!("Failed to read directory: {path:?}"))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && ImageFormat::from_path(path).is_ok())
+                .collect();
+            images.sort();
+            paths.extend(images);
+        } else if input.contains(['*', '?', '[']) {
+            let matches = glob::glob(input).with_context(|| format!("Invalid pattern: {input}"))?;
+            for entry in matches {
+                match entry {
+                    Ok(path) if path.is_file() => paths.push(path),
+                    Ok(_) => {}
+                    Err(e) => {
+                        warnings.warn(format_args!("Skipping unreadable match of {input}: {e}"))
+                    }
+                }
+            }
+        } else if path.is_file() {
+            paths.push(path.to_path_buf());
+        } else {
+            return Err(anyhow!("Input file not found: {input}"));
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Still images shown one after another at a fixed rate
+///
+/// Only the first image is looked at up front; the others are decoded as they are needed and
+/// handed out to be scaled to the size of the first one when no output size is set.
+pub struct ImageSequence {
+    paths: Vec<PathBuf>,
+    fps: f64,
+    size: (u32, u32),
+    scaled: Option<(u32, u32)>,
+    next: usize,
+}
+
+impl ImageSequence {
+    /// Sequence of `paths` at `fps` images per second, 30 if not given
+    pub fn new(paths: Vec<PathBuf>, fps: Option<f64>) -> Result<Self> {
+        let first = paths
+            .first()
+            .ok_or_else(|| anyhow!("Image sequence is empty"))?;
+        let size = image::image_dimensions(first)
+            .with_context(|| format!("Failed to read image: {first:?}"))?;
+
+        Ok(Self {
+            paths,
+            fps: fps.unwrap_or(DEFAULT_PLAY_FPS),
+            size,
+            scaled: None,
+            next: 0,
+        })
+    }
+}
+
+impl FrameSource for ImageSequence {
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn duration(&self) -> Option<f64> {
+        Some(self.paths.len() as f64 / self.fps)
+    }
+
+    fn fps(&self) -> Option<f64> {
+        Some(self.fps)
+    }
+
+    fn scale_to(&mut self, size: (u32, u32)) -> Result<()> {
+        self.scaled = Some(size);
+        Ok(())
+    }
+
+    fn next_frame(&mut self, time: f64) -> Result<Option<SourceFrame>> {
+        let end_of = |index: usize| (index + 1) as f64 / self.fps;
+        while self.next < self.paths.len() && end_of(self.next) <= time {
+            self.next += 1;
+        }
+        let Some(path) = self.paths.get(self.next) else {
+            return Ok(None);
+        };
+
+        let image = image::open(path)
+            .with_context(|| format!("Failed to read image: {path:?}"))?
+            .to_rgb8();
+        let frame = SourceFrame {
+            time: self.next as f64 / self.fps,
+            end: end_of(self.next),
+            number: self.next as u64 + 1,
+            picture: Picture::Unscaled(Unscaled::rgb(image, self.scaled.unwrap_or(self.size))),
+        };
+        self.next += 1;
+        Ok(Some(frame))
+    }
+
+    fn seek(&mut self, time: f64) -> Result<()> {
+        self.next = (time.max(0.0) * self.fps) as usize;
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+use crate::error::Warnings;
+use crate::pack::is_pack;
+use anyhow::{Context, Result};
+use image::RgbImage;
+use std::path::{Path, PathBuf};
+
+pub mod animation;
+pub mod frames;
+pub mod images;
+pub mod scale;
+pub mod video;
+
+use scale::Unscaled;
+
+/// Path that reads a video stream from standard input
+pub const STDIN_PATH: &str = "-";
+
+/// Content of a source frame: pixels still to be rendered, or text rendered earlier
+#[derive(Clone, Debug)]
+pub enum Picture {
+    /// Pixels already scaled to the size set with [`FrameSource::scale_to`]
+    Image(RgbImage),
+    /// Pixels the renderer still has to scale
+    Unscaled(Unscaled),
+    Text(String),
+}
+
+/// Frame of a source together with the time span it is shown for
+#[derive(Clone, Debug)]
+pub struct SourceFrame {
+    /// Presentation time in seconds from the start of the source
+    pub time: f64,
+    /// Time in seconds at which the next frame replaces this one
+    pub end: f64,
+    /// 1-based position of the frame in the source, used in diagnostics
+    pub number: u64,
+    pub picture: Picture,
+}
+
+/// Anything frames can be converted or played from
+///
+/// Sources hand out frames in presentation order, each with the time span it covers, and
+/// skip those that end before the time the caller asks for so that they are never scaled or
+/// read for nothing.
+pub trait FrameSource: Send {
+    /// Size of image frames in pixels, or of text frames in cells
+    fn size(&self) -> (u32, u32);
+
+    /// Length in seconds, when known up front
+    fn duration(&self) -> Option<f64>;
+
+    /// Frame rate the source was made at, when it has one
+    fn fps(&self) -> Option<f64>;
+
+    /// Whether frames are already rendered text rather than images
+    fn is_text(&self) -> bool {
+        false
+    }
+
+    /// Character ramp text frames were rendered with
+    fn charset(&self) -> Option<String> {
+        None
+    }
+
+    /// File whose audio track goes with the frames
+    fn audio_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Whether [`FrameSource::seek`] works; streams read from a pipe can only go forward
+    fn can_seek(&self) -> bool {
+        true
+    }
+
+    /// Scales image frames handed out from now on to `width` x `height` pixels
+    ///
+    /// Sources may leave the scaling to whoever renders the frame by handing out
+    /// [`Picture::Unscaled`] frames of that size. Images keep their own size until this is
+    /// called; text frames are not affected.
+    fn scale_to(&mut self, size: (u32, u32)) -> Result<()>;
+
+    /// Next frame still shown at `time` seconds or later, `None` at the end of the source
+    fn next_frame(&mut self, time: f64) -> Result<Option<SourceFrame>>;
+
+    /// Continues from the frame shown at `time` seconds
+    fn seek(&mut self, time: f64) -> Result<()>;
+}
+
+/// Where a conversion or playback takes its frames from
+pub(crate) enum Input {
+    /// Opened with [`open_source`]
+    Path(PathBuf),
+    /// Opened by the caller
+    Source(Box<dyn FrameSource>),
+}
+
+impl Input {
+    pub fn open(self, rate: Option<f64>, warnings: &Warnings) -> Result<Box<dyn FrameSource>> {
+        match self {
+            Self::Path(path) => open_source(&path, rate, warnings),
+            Self::Source(source) => Ok(source),
+        }
+    }
+
+    /// How the input is shown in messages and manifests
+    pub fn name(&self) -> String {
+        match self {
+            Self::Path(path) => path.to_string_lossy().into_owned(),
+            Self::Source(_) => "custom source".to_string(),
+        }
+    }
+}
+
+/// Opens `path` as whichever kind of source it is
+///
+/// `-` reads a video stream from standard input. Directories of images, glob patterns and
+/// animated GIF, APNG or WebP files are read with the image crate, frame directories and
+/// packed containers written by `convert` as text, and anything else with ffmpeg. `rate`
+/// sets the frame rate of image sequences and frames directories and overrides the one
+/// recorded with converted frames; videos only use it when their stream has none. Problems
+/// that don't stop the source from being read, such as skipped files, go to `warnings`.
+pub fn open_source(
+    path: &Path,
+    rate: Option<f64>,
+    warnings: &Warnings,
+) -> Result<Box<dyn FrameSource>> {
+    if path == Path::new(STDIN_PATH) {
+        return Ok(Box::new(video::VideoSource::open(
+            path,
+            rate,
+            warnings.clone(),
+        )?));
+    }
+
+    let name = path.to_string_lossy();
+    if !path.exists() && name.contains(['*', '?', '[']) {
+        let paths = images::collect_images(&[name.into_owned()], warnings)?;
+        return Ok(Box::new(images::ImageSequence::new(paths, rate)?));
+    }
+    if path.is_dir() {
+        let paths = images::collect_images(&[name.into_owned()], warnings)?;
+        if paths.is_empty() {
+            return Ok(Box::new(frames::TextFrames::open_directory(
+                path, rate, warnings,
+            )?));
+        }
+        return Ok(Box::new(images::ImageSequence::new(paths, rate)?));
+    }
+
+    if is_pack(path) {
+        return Ok(Box::new(frames::TextFrames::open_pack(
+            path, rate, warnings,
+        )?));
+    }
+    if let Some(animation) = animation::Animation::open(path)? {
+        return Ok(Box::new(animation));
+    }
+    Ok(Box::new(
+        video::VideoSource::open(path, rate, warnings.clone())
+            .with_context(|| format!("Unsupported input: {path:?}"))?,
+    ))
+}
+
+/// Number of `fps` slots in `duration` seconds, ignoring rounding errors in the duration
+pub fn slot_count(duration: f64, fps: f64) -> u64 {
+    (duration * fps - 1e-6).ceil().max(0.0) as u64
+}
+
+/// Maps source frames onto the ideal output timeline of `fps` evenly spaced frames per second
+///
+/// Every output slot `k` at `k / fps` seconds shows the source frame on screen at that time,
+/// so frames are duplicated or dropped as needed and the number of emitted frames is the
+/// source duration times `fps`, whatever the source frame rate or time base. Each frame is
+/// handed to `emit` together with the slot and the frame's number in the source.
+pub struct Timeline {
+    fps: f64,
+    next_slot: u64,
+}
+
+impl Timeline {
+    pub fn new(fps: f64) -> Self {
+        Self { fps, next_slot: 0 }
+    }
+
+    fn slot_time(&self, slot: u64) -> f64 {
+        slot as f64 / self.fps
+    }
+
+    /// Continues the timeline at `slot`, e.g. after a seek
+    pub fn restart_at(&mut self, slot: u64) {
+        self.next_slot = slot;
+    }
+
+    /// Emits the next source frame for every slot it covers, returning `false` at the end
+    pub fn advance<F>(&mut self, source: &mut dyn FrameSource, mut emit: F) -> Result<bool>
+    where
+        F: FnMut(u64, &Picture, u64) -> Result<()>,
+    {
+        let Some(frame) = source.next_frame(self.slot_time(self.next_slot))? else {
+            return Ok(false);
+        };
+        while self.slot_time(self.next_slot) < frame.end {
+            emit(self.next_slot, &frame.picture, frame.number)?;
+            self.next_slot += 1;
+        }
+        Ok(true)
+    }
+
+    /// Emits the rest of `source`
+    pub fn run<F>(&mut self, source: &mut dyn FrameSource, mut emit: F) -> Result<()>
+    where
+        F: FnMut(u64, &Picture, u64) -> Result<()>,
+    {
+        while self.advance(source, &mut emit)? {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text frames starting at the given times, the last one lasting until `duration`
+    struct Spans {
+        starts: Vec<f64>,
+        duration: f64,
+        next: usize,
+    }
+
+    impl Spans {
+        fn new(starts: Vec<f64>, duration: f64) -> Self {
+            Self {
+                starts,
+                duration,
+                next: 0,
+            }
+        }
+
+        /// `count` frames at a constant `fps`
+        fn steady(fps: f64, count: u64) -> Self {
+            let starts = (0..count).map(|index| index as f64 / fps).collect();
+            Self::new(starts, count as f64 / fps)
+        }
+
+        fn end_of(&self, index: usize) -> f64 {
+            self.starts.get(index + 1).copied().unwrap_or(self.duration)
+        }
+    }
+
+    impl FrameSource for Spans {
+        fn size(&self) -> (u32, u32) {
+            (1, 1)
+        }
+
+        fn duration(&self) -> Option<f64> {
+            Some(self.duration)
+        }
+
+        fn fps(&self) -> Option<f64> {
+            None
+        }
+
+        fn is_text(&self) -> bool {
+            true
+        }
+
+        fn scale_to(&mut self, _size: (u32, u32)) -> Result<()> {
+            Ok(())
+        }
+
+        fn next_frame(&mut self, time: f64) -> Result<Option<SourceFrame>> {
+            while self.next < self.starts.len() && self.end_of(self.next) <= time {
+                self.next += 1;
+            }
+            if self.next >= self.starts.len() {
+                return Ok(None);
+            }
+
+            let index = self.next;
+            self.next += 1;
+            Ok(Some(SourceFrame {
+                time: self.starts[index],
+                end: self.end_of(index),
+                number: index as u64 + 1,
+                picture: Picture::Text(index.to_string()),
+            }))
+        }
+
+        fn seek(&mut self, time: f64) -> Result<()> {
+            self.next = self
+                .starts
+                .partition_point(|&start| start <= time)
+                .saturating_sub(1);
+            Ok(())
+        }
+    }
+
+    /// Slots emitted for the whole of `source`, with the source frame shown in each
+    fn emitted(source: &mut Spans, fps: f64) -> Vec<(u64, u64)> {
+        let mut slots = Vec::new();
+        Timeline::new(fps)
+            .run(source, |slot, _, number| {
+                slots.push((slot, number));
+                Ok(())
+            })
+            .unwrap();
+        slots
+    }
+
+    #[test]
+    fn timeline_emits_one_frame_per_slot() {
+        for (source_fps, count) in [(25.0, 250), (29.97, 300), (60.0, 7), (12.0, 1)] {
+            for fps in [10.0, 23.976, 29.97, 30.0, 60.0] {
+                let mut source = Spans::steady(source_fps, count);
+                let expected = slot_count(source.duration, fps);
+
+                let slots = emitted(&mut source, fps);
+                assert_eq!(slots.len() as u64, expected, "{source_fps} -> {fps} fps");
+                assert!(slots.iter().map(|&(slot, _)| slot).eq(0..expected));
+            }
+        }
+    }
+
+    #[test]
+    fn timeline_shows_the_frame_on_screen_at_each_slot() {
+        let slots = emitted(&mut Spans::steady(10.0, 20), 4.0);
+        let expected: Vec<_> = (0..8).map(|slot| (slot, slot * 10 / 4 + 1)).collect();
+        assert_eq!(slots, expected);
+    }
+
+    #[test]
+    fn timeline_follows_variable_frame_rates() {
+        // Frame 3 is on screen between two slots and dropped, frame 4 is held for six slots
+        let mut source = Spans::new(vec![0.0, 0.05, 0.25, 0.27, 0.85], 1.0);
+
+        let numbers: Vec<_> = emitted(&mut source, 10.0)
+            .into_iter()
+            .map(|(_, number)| number)
+            .collect();
+        assert_eq!(numbers, [1, 2, 2, 4, 4, 4, 4, 4, 4, 5]);
+        assert_eq!(numbers.len() as u64, slot_count(1.0, 10.0));
+    }
+}
@@ -0,0 +1,118 @@
+use super::video::VideoScaler;
+use anyhow::Result;
+use ffmpeg_next as ffmpeg;
+use image::{
+    Rgb, RgbImage, RgbaImage,
+    imageops::{self, FilterType},
+};
+use std::{fmt, sync::Arc};
+
+/// Decoded frame still to be scaled to the size set with [`FrameSource::scale_to`]
+///
+/// Sources hand frames out unscaled so that scaling runs on the threads rendering them
+/// rather than on the one decoding. Clones share the decoded pixels.
+///
+/// [`FrameSource::scale_to`]: super::FrameSource::scale_to
+#[derive(Clone)]
+pub struct Unscaled {
+    pixels: Arc<Pixels>,
+    size: (u32, u32),
+}
+
+enum Pixels {
+    Video(ffmpeg::frame::Video),
+    Rgb(RgbImage),
+    /// Flattened onto black once scaled
+    Rgba(RgbaImage),
+}
+
+impl Unscaled {
+    fn new(pixels: Pixels, size: (u32, u32)) -> Self {
+        Self {
+            pixels: Arc::new(pixels),
+            size,
+        }
+    }
+
+    pub(crate) fn video(frame: ffmpeg::frame::Video, size: (u32, u32)) -> Self {
+        Self::new(Pixels::Video(frame), size)
+    }
+
+    pub(crate) fn rgb(image: RgbImage, size: (u32, u32)) -> Self {
+        Self::new(Pixels::Rgb(image), size)
+    }
+
+    pub(crate) fn rgba(image: RgbaImage, size: (u32, u32)) -> Self {
+        Self::new(Pixels::Rgba(image), size)
+    }
+
+    /// Size in pixels the frame is scaled to
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Scales the frame, reusing what `scaler` kept from earlier frames
+    pub fn scale(&self, scaler: &mut FrameScaler) -> Result<RgbImage> {
+        let (width, height) = self.size;
+        match &*self.pixels {
+            Pixels::Video(frame) => VideoScaler::scale(&mut scaler.video, frame, self.size),
+            Pixels::Rgb(image) if image.dimensions() == self.size => Ok(image.clone()),
+            Pixels::Rgb(image) => Ok(imageops::resize(image, width, height, FilterType::Triangle)),
+            Pixels::Rgba(image) => {
+                let scaled = imageops::resize(image, width, height, FilterType::Triangle);
+                Ok(RgbImage::from_fn(width, height, |x, y| {
+                    let [r, g, b, a] = scaled.get_pixel(x, y).0;
+                    let blend = |channel: u8| (channel as u16 * a as u16 / 255) as u8;
+                    Rgb([blend(r), blend(g), blend(b)])
+                }))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Unscaled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Unscaled")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What scaling [`Unscaled`] frames keeps from one frame to the next
+///
+/// Every thread that scales frames has its own.
+#[derive(Default)]
+pub struct FrameScaler {
+    video: Option<VideoScaler>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn images_are_scaled_to_the_requested_size() {
+        let image = RgbImage::from_pixel(8, 4, Rgb([200, 100, 50]));
+        let frame = Unscaled::rgb(image.clone(), (4, 2));
+
+        let scaled = frame.scale(&mut FrameScaler::default()).unwrap();
+        assert_eq!(scaled.dimensions(), (4, 2));
+        assert_eq!(scaled.get_pixel(1, 1), &Rgb([200, 100, 50]));
+
+        let unchanged = Unscaled::rgb(image.clone(), (8, 4));
+        assert_eq!(unchanged.scale(&mut FrameScaler::default()).unwrap(), image);
+    }
+
+    #[test]
+    fn transparency_is_flattened_onto_black() {
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([200, 100, 50, 255]));
+        image.put_pixel(1, 0, Rgba([200, 100, 50, 0]));
+
+        let flat = Unscaled::rgba(image, (2, 1))
+            .scale(&mut FrameScaler::default())
+            .unwrap();
+        assert_eq!(flat.get_pixel(0, 0), &Rgb([200, 100, 50]));
+        assert_eq!(flat.get_pixel(1, 0), &Rgb([0, 0, 0]));
+    }
+}
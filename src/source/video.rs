@@ -0,0 +1,371 @@
+use super::{FrameSource, Picture, STDIN_PATH, SourceFrame, scale::Unscaled};
+use crate::error::Warnings;
+use crate::types::consts::{AV_TIME_BASE, DEFAULT_PLAY_FPS, EAGAIN, NO_PTS};
+use anyhow::{Context, Result, anyhow};
+use ffmpeg::software::scaling;
+use ffmpeg_next as ffmpeg;
+use image::{ImageBuffer, RgbImage};
+use std::{
+    mem,
+    path::{Path, PathBuf},
+};
+
+/// Copies the packed RGB24 plane of a frame into an image buffer, dropping row padding
+pub fn frame_to_image(frame: &ffmpeg::frame::Video) -> Option<RgbImage> {
+    let (width, height) = (frame.width(), frame.height());
+    let row_len = width as usize * 3;
+    let stride = frame.stride(0);
+    if stride < row_len {
+        return None;
+    }
+
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    for row in frame.data(0).chunks(stride).take(height as usize) {
+        pixels.extend_from_slice(row.get(..row_len)?);
+    }
+
+    ImageBuffer::from_raw(width, height, pixels)
+}
+
+/// Moves every stream of `input` to the keyframe at or before `seconds` of container time
+pub fn seek_input(
+    input: &mut ffmpeg::format::context::Input,
+    seconds: f64,
+) -> Result<(), ffmpeg::Error> {
+    let timestamp = (seconds * AV_TIME_BASE) as i64;
+    input.seek(timestamp, ..timestamp)
+}
+
+/// Pull-style decoder for the best video stream of a media file
+///
+/// Frames are decoded one at a time into an internal buffer and reported with their
+/// presentation time in seconds, relative to the start of the stream.
+pub struct VideoDecoder {
+    input: ffmpeg::format::context::Input,
+    decoder: ffmpeg::codec::decoder::Video,
+    stream_index: usize,
+    time_base: f64,
+    start_pts: i64,
+    duration: Option<f64>,
+    /// Average frame rate of the stream, when it reports a usable one
+    fps: Option<f64>,
+    /// Fallback spacing for frames without timestamps and for the length of the last frame
+    frame_interval: f64,
+    frame: ffmpeg::frame::Video,
+    eof_sent: bool,
+    decoded_frames: u64,
+    last_time: Option<f64>,
+    /// Told about decoding errors that only cost the frames affected
+    warnings: Warnings,
+}
+
+impl VideoDecoder {
+    /// Opens `path`, or standard input for `-`; `fallback_fps` spaces frames when the stream
+    /// has no usable frame rate
+    pub fn open(path: &Path, fallback_fps: f64, warnings: Warnings) -> Result<Self> {
+        ffmpeg::init().context("Failed to initialize FFmpeg")?;
+
+        let input = if path == Path::new(STDIN_PATH) {
+            ffmpeg::format::input(&"pipe:0").context("Failed to open standard input")?
+        } else {
+            if !path.exists() {
+                return Err(anyhow!("Input file not found: {path:?}"));
+            }
+            ffmpeg::format::input(&path)
+                .with_context(|| format!("Failed to open input file: {path:?}"))?
+        };
+
+        let input_stream = input
+            .streams()
+            .best(ffmpeg::media::Type::Video)
+            .ok_or_else(|| anyhow!("Could not find video stream in input file"))?;
+        let stream_index = input_stream.index();
+        let codec_parameters = input_stream.parameters();
+
+        let decoder = ffmpeg::codec::context::Context::from_parameters(codec_parameters)?
+            .decoder()
+            .video()?;
+        let time_base: f64 = input_stream.time_base().into();
+        let video_fps: f64 = input_stream.avg_frame_rate().into();
+        let start_pts = match input_stream.start_time() {
+            NO_PTS => 0,
+            pts => pts,
+        };
+        let duration = match (input_stream.duration(), input.duration()) {
+            (NO_PTS | 0, NO_PTS | 0) => None,
+            (NO_PTS | 0, container) => Some(container as f64 / AV_TIME_BASE),
+            (duration, _) => Some(duration as f64 * time_base),
+        };
+        let frame_interval = if video_fps > 0.0 {
+            1.0 / video_fps
+        } else {
+            1.0 / fallback_fps
+        };
+
+        Ok(Self {
+            input,
+            decoder,
+            stream_index,
+            time_base,
+            start_pts,
+            duration,
+            fps: (video_fps > 0.0).then_some(video_fps),
+            frame_interval,
+            frame: ffmpeg::frame::Video::empty(),
+            eof_sent: false,
+            decoded_frames: 0,
+            last_time: None,
+            warnings,
+        })
+    }
+
+    /// Frame size in pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.decoder.width(), self.decoder.height())
+    }
+
+    /// Stream duration in seconds, when the container reports one
+    pub fn duration(&self) -> Option<f64> {
+        self.duration
+    }
+
+    /// Time at which the last decoded frame stops being shown
+    ///
+    /// That is the end of the stream, or one source frame after the last one if unknown.
+    pub fn end_time(&self) -> f64 {
+        self.duration
+            .or(self.last_time.map(|time| time + self.frame_interval))
+            .unwrap_or(0.0)
+    }
+
+    /// Decodes the next frame, returning its presentation time or `None` at the end
+    pub fn next_frame(&mut self) -> Result<Option<f64>> {
+        loop {
+            match self.decoder.receive_frame(&mut self.frame) {
+                Ok(()) => {
+                    self.decoded_frames += 1;
+                    let time = match self.frame.timestamp().or(self.frame.pts()) {
+                        Some(pts) => (pts - self.start_pts) as f64 * self.time_base,
+                        None => self
+                            .last_time
+                            .map_or(0.0, |time| time + self.frame_interval),
+                    };
+                    self.last_time = Some(time);
+                    return Ok(Some(time));
+                }
+                Err(ffmpeg::Error::Eof) => return Ok(None),
+                Err(ffmpeg::Error::Other { errno }) if errno == EAGAIN => {}
+                Err(_) if self.eof_sent => return Ok(None),
+                Err(e) => self
+                    .warnings
+                    .warn(format_args!("Error receiving frame: {e}")),
+            }
+
+            if self.eof_sent {
+                return Ok(None);
+            }
+            self.feed_packet()?;
+        }
+    }
+
+    /// Sends the next packet of the video stream to the decoder, or EOF when there are none left
+    fn feed_packet(&mut self) -> Result<()> {
+        loop {
+            let Some((stream, packet)) = self.input.packets().next() else {
+                if let Err(e) = self.decoder.send_eof()
+                    && e != ffmpeg::Error::Eof
+                {
+                    self.warnings
+                        .warn(format_args!("Failed to send final EOF to decoder: {e}"));
+                }
+                self.eof_sent = true;
+                return Ok(());
+            };
+            if stream.index() != self.stream_index {
+                continue;
+            }
+
+            return match self.decoder.send_packet(&packet) {
+                Ok(()) => Ok(()),
+                Err(e) if matches!(e, ffmpeg::Error::Other { .. }) => {
+                    self.warnings
+                        .warn(format_args!("Non-fatal error when sending packet: {e}"));
+                    Ok(())
+                }
+                Err(e) => Err(anyhow!("Failed to send packet to decoder: {}", e)),
+            };
+        }
+    }
+
+    /// Repositions the stream at or before `time` seconds
+    ///
+    /// Decoding resumes from the preceding keyframe, so frames before `time` may still show
+    /// up; [`Timeline`] skips them naturally.
+    pub fn seek(&mut self, time: f64) -> Result<()> {
+        let absolute = time.max(0.0) + self.start_pts as f64 * self.time_base;
+        seek_input(&mut self.input, absolute)
+            .with_context(|| format!("Failed to seek to {time:.2}s"))?;
+        self.decoder.flush();
+        self.eof_sent = false;
+        self.last_time = None;
+        Ok(())
+    }
+}
+
+/// `scaling::Context` is only `!Send` because it wraps a raw pointer
+struct Scaler(scaling::Context);
+
+// SAFETY: a libswscale context has no thread affinity and is only used through `&mut self` of
+// its owner, so moving it to another thread is fine
+unsafe impl Send for Scaler {}
+
+/// Converts decoded frames of one pixel format and size to RGB24 of another size
+pub(crate) struct VideoScaler {
+    input: (ffmpeg::format::Pixel, u32, u32),
+    output: (u32, u32),
+    scaler: Scaler,
+}
+
+impl VideoScaler {
+    /// Scales `frame` to `size`, replacing `scaler` first if it was made for other frames
+    pub fn scale(
+        scaler: &mut Option<Self>,
+        frame: &ffmpeg::frame::Video,
+        size: (u32, u32),
+    ) -> Result<RgbImage> {
+        let input = (frame.format(), frame.width(), frame.height());
+        let scaler = match scaler
+            .take()
+            .filter(|scaler| scaler.input == input && scaler.output == size)
+        {
+            Some(reused) => scaler.insert(reused),
+            None => {
+                let context = scaling::Context::get(
+                    input.0,
+                    input.1,
+                    input.2,
+                    ffmpeg::format::Pixel::RGB24,
+                    size.0,
+                    size.1,
+                    scaling::Flags::BILINEAR,
+                )
+                .context("Failed to create frame scaler")?;
+                scaler.insert(Self {
+                    input,
+                    output: size,
+                    scaler: Scaler(context),
+                })
+            }
+        };
+
+        let mut rgb_frame = ffmpeg::frame::Video::empty();
+        scaler
+            .scaler
+            .0
+            .run(frame, &mut rgb_frame)
+            .context("Failed to scale frame")?;
+        frame_to_image(&rgb_frame).ok_or_else(|| anyhow!("Failed to create image buffer"))
+    }
+}
+
+/// Video file or stream as a [`FrameSource`]
+///
+/// Every decoded frame is held back until the next one arrives and tells when it ends, so
+/// frames that cover no requested time are dropped without ever being handed out. Frames are
+/// handed out unscaled, in the decoder's pixel format.
+pub struct VideoSource {
+    video: VideoDecoder,
+    /// File the video came from; `None` for standard input
+    path: Option<PathBuf>,
+    size: (u32, u32),
+    held: ffmpeg::frame::Video,
+    held_time: Option<f64>,
+    held_frame: u64,
+}
+
+impl VideoSource {
+    /// Opens `path`, or standard input for `-`; `fallback_fps` spaces frames when the stream
+    /// has no usable frame rate
+    pub fn open(path: &Path, fallback_fps: Option<f64>, warnings: Warnings) -> Result<Self> {
+        let video = VideoDecoder::open(path, fallback_fps.unwrap_or(DEFAULT_PLAY_FPS), warnings)?;
+        Ok(Self {
+            size: video.size(),
+            path: (path != Path::new(STDIN_PATH)).then(|| path.to_path_buf()),
+            video,
+            held: ffmpeg::frame::Video::empty(),
+            held_time: None,
+            held_frame: 0,
+        })
+    }
+}
+
+impl FrameSource for VideoSource {
+    fn size(&self) -> (u32, u32) {
+        self.video.size()
+    }
+
+    fn duration(&self) -> Option<f64> {
+        self.video.duration()
+    }
+
+    fn fps(&self) -> Option<f64> {
+        self.video.fps
+    }
+
+    fn audio_path(&self) -> Option<PathBuf> {
+        self.path.clone()
+    }
+
+    fn can_seek(&self) -> bool {
+        self.path.is_some()
+    }
+
+    fn scale_to(&mut self, size: (u32, u32)) -> Result<()> {
+        self.size = size;
+        Ok(())
+    }
+
+    fn next_frame(&mut self, time: f64) -> Result<Option<SourceFrame>> {
+        loop {
+            let next_time = self.video.next_frame()?;
+            let Some(held_time) = self.held_time else {
+                // Nothing held yet, only at the start or right after a seek
+                let Some(next_time) = next_time else {
+                    return Ok(None);
+                };
+                mem::swap(&mut self.held, &mut self.video.frame);
+                self.held_time = Some(next_time);
+                self.held_frame = self.video.decoded_frames;
+                continue;
+            };
+
+            let end = next_time.unwrap_or_else(|| self.video.end_time());
+            // A frame that is shown is handed out, otherwise the decoder can reuse its buffer
+            let shown =
+                (end > time).then(|| mem::replace(&mut self.held, ffmpeg::frame::Video::empty()));
+            let number = self.held_frame;
+
+            mem::swap(&mut self.held, &mut self.video.frame);
+            self.held_time = next_time;
+            self.held_frame = self.video.decoded_frames;
+
+            if let Some(frame) = shown {
+                return Ok(Some(SourceFrame {
+                    time: held_time,
+                    end,
+                    number,
+                    picture: Picture::Unscaled(Unscaled::video(frame, self.size)),
+                }));
+            }
+            if next_time.is_none() {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn seek(&mut self, time: f64) -> Result<()> {
+        self.video.seek(time)?;
+        self.held_time = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use crate::auto_levels::AutoLevels;
+use crate::tone_options::ToneOptions;
+use image::{Rgb, RgbImage};
+use std::borrow::Cow;
+
+/// Share of the darkest and of the brightest pixels clipped by `--auto-levels stretch`, so a
+/// few specks of noise don't decide the range
+const STRETCH_CLIP: f32 = 0.005;
+
+/// Rec. 601 luminance of a pixel as a histogram bucket
+fn luma_bucket(pixel: &Rgb<u8>) -> usize {
+    let [r, g, b] = pixel.0;
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as usize
+}
+
+/// Maps every level to its place in the range the frame's levels are spread over, in
+/// `0.0..=1.0`
+fn auto_levels(img: &RgbImage, mode: AutoLevels) -> [f32; 256] {
+    let mut histogram = [0u32; 256];
+    for pixel in img.pixels() {
+        histogram[luma_bucket(pixel)] += 1;
+    }
+    let total = histogram.iter().sum::<u32>().max(1) as f32;
+    let mut cumulative = [0.0f32; 256];
+    let mut sum = 0;
+    for (level, count) in histogram.iter().enumerate() {
+        sum += count;
+        cumulative[level] = sum as f32 / total;
+    }
+
+    match mode {
+        AutoLevels::Stretch => {
+            let low = cumulative.iter().position(|&share| share > STRETCH_CLIP);
+            let high = cumulative
+                .iter()
+                .position(|&share| share >= 1.0 - STRETCH_CLIP);
+            let (low, high) = match (low, high) {
+                (Some(low), Some(high)) if high > low => (low as f32, high as f32),
+                // A flat frame has no range to stretch
+                _ => (0.0, 255.0),
+            };
+            std::array::from_fn(|level| ((level as f32 - low) / (high - low)).clamp(0.0, 1.0))
+        }
+        AutoLevels::Equalize => {
+            // Shift so that the darkest level present maps to black
+            let first = cumulative.iter().copied().find(|&share| share > 0.0);
+            let first = first.unwrap_or(0.0);
+            let span = (1.0 - first).max(f32::EPSILON);
+            cumulative.map(|share| ((share - first) / span).clamp(0.0, 1.0))
+        }
+    }
+}
+
+/// Applies `tone` to a copy of `img`, or hands `img` back when `tone` leaves it untouched
+pub fn adjust<'a>(img: &'a RgbImage, tone: &ToneOptions) -> Cow<'a, RgbImage> {
+    if tone.is_neutral() {
+        return Cow::Borrowed(img);
+    }
+    let levels = match tone.auto_levels {
+        Some(mode) => auto_levels(img, mode),
+        None => std::array::from_fn(|level| level as f32 / 255.0),
+    };
+
+    // Everything but saturation treats channels alike, so it fits in a lookup table
+    let inverse_gamma = 1.0 / tone.gamma;
+    let table: [f32; 256] = levels.map(|value| {
+        let value = value + tone.brightness;
+        let value = (value - 0.5) * tone.contrast + 0.5;
+        value.clamp(0.0, 1.0).powf(inverse_gamma) * 255.0
+    });
+
+    let mut adjusted = img.clone();
+    for pixel in adjusted.pixels_mut() {
+        let channels = pixel.0.map(|channel| table[channel as usize]);
+        let [r, g, b] = channels;
+        let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+        pixel.0 = channels.map(|channel| {
+            (luma + (channel - luma) * tone.saturation)
+                .clamp(0.0, 255.0)
+                .round() as u8
+        });
+    }
+    Cow::Owned(adjusted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gray image with one pixel per level in `levels`
+    fn grays(levels: &[u8]) -> RgbImage {
+        RgbImage::from_fn(levels.len() as u32, 1, |x, _| Rgb([levels[x as usize]; 3]))
+    }
+
+    fn levels(img: &RgbImage) -> Vec<u8> {
+        img.pixels().map(|pixel| pixel.0[0]).collect()
+    }
+
+    #[test]
+    fn neutral_tone_leaves_frames_untouched() {
+        let img = grays(&[0, 100, 255]);
+        assert!(matches!(
+            adjust(&img, &ToneOptions::default()),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn brightness_and_contrast_clamp_to_the_channel_range() {
+        let img = RgbImage::from_fn(3, 1, |x, _| Rgb([[10, 128, 250][x as usize], 60, 200]));
+        let brighter = ToneOptions {
+            brightness: 1.0,
+            ..ToneOptions::default()
+        };
+        assert!(adjust(&img, &brighter).pixels().all(|p| p.0 == [255; 3]));
+        let darker = ToneOptions {
+            brightness: -1.0,
+            ..ToneOptions::default()
+        };
+        assert!(adjust(&img, &darker).pixels().all(|p| p.0 == [0; 3]));
+
+        let punchier = ToneOptions {
+            contrast: 4.0,
+            ..ToneOptions::default()
+        };
+        assert_eq!(
+            levels(&adjust(&grays(&[20, 60, 200, 240]), &punchier)),
+            [0, 0, 255, 255]
+        );
+    }
+
+    #[test]
+    fn stretch_spreads_a_low_contrast_frame_over_the_full_range() {
+        let img = grays(&(100..=140).collect::<Vec<_>>());
+        let stretch = ToneOptions {
+            auto_levels: Some(AutoLevels::Stretch),
+            ..ToneOptions::default()
+        };
+
+        let stretched = levels(&adjust(&img, &stretch));
+        assert_eq!((stretched[0], stretched[40]), (0, 255));
+        assert!(stretched.is_sorted());
+    }
+
+    #[test]
+    fn equalize_gives_equally_common_levels_equal_steps() {
+        let img = grays(&[40, 40, 80, 80, 120, 120, 160, 160]);
+        let equalize = ToneOptions {
+            auto_levels: Some(AutoLevels::Equalize),
+            ..ToneOptions::default()
+        };
+
+        assert_eq!(
+            levels(&adjust(&img, &equalize)),
+            [0, 0, 85, 85, 170, 170, 255, 255]
+        );
+    }
+}
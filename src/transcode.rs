@@ -0,0 +1,239 @@
+use crate::types::{audio_format::AudioFormat, consts::NO_PTS};
+use anyhow::{Context, Result, anyhow};
+use ffmpeg::{Rescale, codec, filter, format, frame, media};
+use ffmpeg_next as ffmpeg;
+use std::path::Path;
+
+/// Re-encodes the best audio stream of `input` into a standalone `output` file
+///
+/// Samples pass through a filter graph that converts them to the encoder's sample format and
+/// layout and cuts them into frames of the size the encoder expects, keeping their timestamps
+/// relative to the start of the stream. Returns `Ok(false)` when the input has no audio
+/// stream.
+pub fn transcode_audio(input: &Path, output: &Path, audio_format: AudioFormat) -> Result<bool> {
+    ffmpeg::init().context("Failed to initialize FFmpeg")?;
+
+    let mut ictx =
+        format::input(&input).with_context(|| format!("Failed to open input file: {input:?}"))?;
+    let Some(input_stream) = ictx.streams().best(media::Type::Audio) else {
+        return Ok(false);
+    };
+    let stream_index = input_stream.index();
+    let input_time_base = input_stream.time_base();
+    let start_pts = match input_stream.start_time() {
+        NO_PTS => 0,
+        pts => pts,
+    };
+    let decoder = codec::context::Context::from_parameters(input_stream.parameters())?
+        .decoder()
+        .audio()?;
+
+    let mut octx = format::output(&output)
+        .with_context(|| format!("Failed to create audio file: {output:?}"))?;
+    // An encoder from an external library beats FFmpeg's own where both exist, as FFmpeg's
+    // Vorbis encoder is experimental
+    let codec = audio_format
+        .encoder_name()
+        .and_then(ffmpeg::encoder::find_by_name)
+        .or_else(|| ffmpeg::encoder::find(audio_format.codec_id()))
+        .ok_or_else(|| anyhow!("FFmpeg has no {audio_format:?} encoder"))?
+        .audio()?;
+    let global_header = octx
+        .format()
+        .flags()
+        .contains(format::flag::Flags::GLOBAL_HEADER);
+
+    let rate = decoder.rate();
+    let input_layout = if decoder.channel_layout().is_empty() {
+        ffmpeg::ChannelLayout::default(decoder.channels().into())
+    } else {
+        decoder.channel_layout()
+    };
+    let channel_layout = codec
+        .channel_layouts()
+        .map(|layouts| layouts.best(input_layout.channels()))
+        .unwrap_or(input_layout);
+    let sample_format = codec
+        .formats()
+        .and_then(|mut formats| formats.next())
+        .ok_or_else(|| anyhow!("{audio_format:?} encoder reports no sample formats"))?;
+
+    let mut stream = octx.add_stream(codec)?;
+    let mut encoder = codec::context::Context::from_parameters(stream.parameters())?
+        .encoder()
+        .audio()?;
+    if global_header {
+        encoder.set_flags(codec::flag::Flags::GLOBAL_HEADER);
+    }
+    encoder.set_rate(rate as i32);
+    encoder.set_channel_layout(channel_layout);
+    encoder.set_format(sample_format);
+    encoder.set_time_base((1, rate as i32));
+    if codec
+        .capabilities()
+        .contains(codec::capabilities::Capabilities::EXPERIMENTAL)
+    {
+        encoder.compliance(codec::Compliance::Experimental);
+    }
+    stream.set_time_base((1, rate as i32));
+    let encoder = encoder
+        .open_as(codec)
+        .with_context(|| format!("Failed to open {audio_format:?} encoder"))?;
+    stream.set_parameters(&encoder);
+
+    let mut graph = filter::Graph::new();
+    let args = format!(
+        "time_base={}/{}:sample_rate={rate}:sample_fmt={}:channel_layout=0x{:x}",
+        input_time_base.numerator(),
+        input_time_base.denominator(),
+        decoder.format().name(),
+        input_layout.bits()
+    );
+    graph.add(
+        &filter::find("abuffer").context("Missing abuffer filter")?,
+        "in",
+        &args,
+    )?;
+    graph.add(
+        &filter::find("abuffersink").context("Missing abuffersink filter")?,
+        "out",
+        "",
+    )?;
+    {
+        let mut out = graph.get("out").context("Missing filter output")?;
+        out.set_sample_format(sample_format);
+        out.set_channel_layout(channel_layout);
+        out.set_sample_rate(rate);
+        if !codec
+            .capabilities()
+            .contains(codec::capabilities::Capabilities::VARIABLE_FRAME_SIZE)
+        {
+            out.sink().set_frame_size(encoder.frame_size());
+        }
+    }
+    graph.output("in", 0)?.input("out", 0)?.parse("anull")?;
+    graph.validate()?;
+    let filtered_time_base = graph
+        .get("out")
+        .context("Missing filter output")?
+        .sink()
+        .time_base();
+
+    octx.write_header()
+        .with_context(|| format!("Failed to write audio file header: {output:?}"))?;
+    // The muxer may pick its own time base while writing the header
+    let stream_time_base = octx
+        .stream(0)
+        .context("Audio stream vanished from output")?
+        .time_base();
+
+    let mut transcoder = Transcoder {
+        decoder,
+        graph,
+        encoder,
+        octx,
+        rate,
+        stream_time_base,
+        start_pts,
+        filtered_time_base,
+        next_pts: 0,
+    };
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+        match transcoder.decoder.send_packet(&packet) {
+            Ok(()) | Err(ffmpeg::Error::Other { .. }) => {}
+            Err(e) => return Err(anyhow!("Failed to send packet to audio decoder: {}", e)),
+        }
+        transcoder.decode_pending()?;
+    }
+    transcoder.finish()?;
+
+    Ok(true)
+}
+
+/// Decoder, conversion graph and encoder of one audio stream being transcoded
+struct Transcoder {
+    decoder: codec::decoder::Audio,
+    graph: filter::Graph,
+    encoder: codec::encoder::audio::Encoder,
+    octx: format::context::Output,
+    rate: u32,
+    stream_time_base: ffmpeg::Rational,
+    /// Timestamp of the first sample in the input time base, mapped to 0 in the output
+    start_pts: i64,
+    /// Time base of frames coming out of the filter graph
+    filtered_time_base: ffmpeg::Rational,
+    /// End of the last frame sent to the encoder, in samples; frames without a timestamp
+    /// start there and none may start before it
+    next_pts: i64,
+}
+
+impl Transcoder {
+    fn decode_pending(&mut self) -> Result<()> {
+        let mut decoded = frame::Audio::empty();
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            decoded.set_pts(decoded.timestamp().map(|pts| pts - self.start_pts));
+            self.graph
+                .get("in")
+                .context("Missing filter input")?
+                .source()
+                .add(&decoded)?;
+            self.encode_filtered()?;
+        }
+        Ok(())
+    }
+
+    fn encode_filtered(&mut self) -> Result<()> {
+        let mut filtered = frame::Audio::empty();
+        while self
+            .graph
+            .get("out")
+            .context("Missing filter output")?
+            .sink()
+            .frame(&mut filtered)
+            .is_ok()
+        {
+            let pts = filtered.pts().map_or(self.next_pts, |pts| {
+                let pts = pts.rescale(self.filtered_time_base, (1, self.rate as i32));
+                // Rounding may overlap the previous frame by a sample
+                pts.max(self.next_pts)
+            });
+            filtered.set_pts(Some(pts));
+            self.next_pts = pts + filtered.samples() as i64;
+            self.encoder.send_frame(&filtered)?;
+            self.write_packets()?;
+        }
+        Ok(())
+    }
+
+    fn write_packets(&mut self) -> Result<()> {
+        let mut packet = ffmpeg::Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(0);
+            packet.rescale_ts((1, self.rate as i32), self.stream_time_base);
+            packet.write_interleaved(&mut self.octx)?;
+        }
+        Ok(())
+    }
+
+    /// Drains every stage in order and finalizes the file
+    fn finish(mut self) -> Result<()> {
+        let _ = self.decoder.send_eof();
+        self.decode_pending()?;
+        self.graph
+            .get("in")
+            .context("Missing filter input")?
+            .source()
+            .flush()?;
+        self.encode_filtered()?;
+        self.encoder.send_eof()?;
+        self.write_packets()?;
+
+        self.octx
+            .write_trailer()
+            .context("Failed to finish audio file")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use clap::ValueEnum;
+use ffmpeg_next::codec::Id;
+
+/// Codec `convert` stores the soundtrack with, picked among what rodio can also decode
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioFormat {
+    /// Lossless FLAC
+    Flac,
+    /// Ogg Vorbis, much smaller
+    Vorbis,
+}
+
+impl AudioFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Flac => "flac",
+            Self::Vorbis => "ogg",
+        }
+    }
+
+    /// Encoder looked up before FFmpeg's own one for [`AudioFormat::codec_id`]
+    pub fn encoder_name(self) -> Option<&'static str> {
+        match self {
+            Self::Flac => None,
+            Self::Vorbis => Some("libvorbis"),
+        }
+    }
+
+    pub fn codec_id(self) -> Id {
+        match self {
+            Self::Flac => Id::FLAC,
+            Self::Vorbis => Id::VORBIS,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use clap::ValueEnum;
+
+/// How `--auto-levels` spreads the brightness of every frame over the full range
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutoLevels {
+    /// Stretch the darkest and brightest tones to black and white
+    Stretch,
+    /// Equalize the histogram so every brightness level is about equally common
+    Equalize,
+}
@@ -0,0 +1,114 @@
+use std::str::FromStr;
+use sysx::utils::ascii::CHAR_SET_VERY_DETAILED;
+
+const CHAR_SET_SIMPLE: &str = " .:-=+*#%@";
+const CHAR_SET_BLOCKS: &str = " ░▒▓█";
+const CHAR_SET_DIGITS: &str = " 1723459680";
+
+/// Character ramp ordered from darkest to lightest, either a named preset or a literal string
+///
+/// A ramp needs at least two characters to tell light from dark.
+#[derive(Clone, Debug)]
+pub struct Charset {
+    ramp: Vec<char>,
+}
+
+impl Charset {
+    pub fn ramp(&self) -> &[char] {
+        &self.ramp
+    }
+
+    /// Reverses the ramp, for terminals with a light background
+    pub fn inverted(mut self) -> Self {
+        self.ramp.reverse();
+        self
+    }
+}
+
+impl Default for Charset {
+    /// The `detailed` preset
+    fn default() -> Self {
+        Self {
+            ramp: CHAR_SET_VERY_DETAILED.chars().collect(),
+        }
+    }
+}
+
+impl FromStr for Charset {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let ramp = match value {
+            "simple" => CHAR_SET_SIMPLE.chars().collect(),
+            "detailed" => CHAR_SET_VERY_DETAILED.chars().collect(),
+            "blocks" => CHAR_SET_BLOCKS.chars().collect(),
+            "digits" => CHAR_SET_DIGITS.chars().collect(),
+            "inverted" => CHAR_SET_VERY_DETAILED.chars().rev().collect(),
+            literal => literal.chars().collect::<Vec<_>>(),
+        };
+
+        if ramp.len() < 2 {
+            return Err("charset must contain at least two characters".to_string());
+        }
+        Ok(Self { ramp })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RenderOptions;
+
+    fn ramp(value: &str) -> String {
+        value.parse::<Charset>().unwrap().ramp().iter().collect()
+    }
+
+    #[test]
+    fn presets_are_looked_up_by_name() {
+        assert_eq!(ramp("simple"), CHAR_SET_SIMPLE);
+        assert_eq!(ramp("detailed"), CHAR_SET_VERY_DETAILED);
+        assert_eq!(ramp("blocks"), CHAR_SET_BLOCKS);
+        assert_eq!(ramp("digits"), CHAR_SET_DIGITS);
+        assert_eq!(
+            ramp("inverted"),
+            CHAR_SET_VERY_DETAILED.chars().rev().collect::<String>()
+        );
+        assert_eq!(
+            Charset::default().ramp(),
+            ramp("detailed").chars().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn anything_else_is_a_literal_ramp() {
+        assert_eq!(ramp(" .oO"), " .oO");
+        assert_eq!(ramp("·•●"), "·•●");
+    }
+
+    #[test]
+    fn ramps_need_two_characters() {
+        assert!("".parse::<Charset>().is_err());
+        assert!("#".parse::<Charset>().is_err());
+        assert!("█".parse::<Charset>().is_err());
+        assert_eq!(ramp(" #"), " #");
+    }
+
+    #[test]
+    fn invert_reverses_the_chosen_ramp() {
+        let options = RenderOptions {
+            charset: "simple".parse().unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.ramp().ramp(),
+            ramp("simple").chars().collect::<Vec<_>>()
+        );
+
+        let inverted = RenderOptions {
+            invert: true,
+            ..options
+        };
+        let reversed: String = CHAR_SET_SIMPLE.chars().rev().collect();
+        assert_eq!(inverted.ramp().ramp(), reversed.chars().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,21 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Color depth used for the escape sequences written into converted frames
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// 24-bit RGB escape sequences
+    Truecolor,
+    /// xterm 256-color palette
+    #[value(name = "256")]
+    #[serde(rename = "256")]
+    Ansi256,
+    /// Basic 16-color palette
+    #[value(name = "16")]
+    #[serde(rename = "16")]
+    Ansi16,
+    /// Plain characters without escape sequences
+    #[default]
+    None,
+}
@@ -1 +1,40 @@
+use std::time::Duration;
+
 pub const EAGAIN: i32 = 11;
+
+/// How often the paused player checks for key presses
+pub const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// FFmpeg's `AV_NOPTS_VALUE`, used for unknown timestamps and durations
+pub const NO_PTS: i64 = i64::MIN;
+
+/// FFmpeg's `AV_TIME_BASE`: container-level durations are expressed in microseconds
+pub const AV_TIME_BASE: f64 = 1_000_000.0;
+
+/// File in the output directory recording the character ramp used for conversion
+pub const CHARSET_FILE: &str = "charset";
+
+/// Extension given to packed containers written by `convert --format pack`
+pub const PACK_EXTENSION: &str = "a4p";
+
+/// Extension given to asciicast recordings written by `convert --format cast`
+pub const CAST_EXTENSION: &str = "cast";
+
+/// Output width in cells when neither a size nor `--auto-size` is given
+pub const DEFAULT_WIDTH: u32 = 100;
+
+/// Conversion FPS when `--fps` is not given
+pub const DEFAULT_CONVERT_FPS: f64 = 15.0;
+
+/// Playback FPS when neither `--fps` nor a manifest gives one
+pub const DEFAULT_PLAY_FPS: f64 = 30.0;
+
+/// Frames `play` keeps loaded ahead of the one on screen
+pub const READ_AHEAD_FRAMES: usize = 64;
+
+/// Metadata file written next to converted frames; a pack gets a sibling with this extension
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const MANIFEST_EXTENSION: &str = "json";
+
+/// Name of the soundtrack `convert --audio` stores in an output directory
+pub const AUDIO_FILE_STEM: &str = "audio";
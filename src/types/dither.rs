@@ -0,0 +1,17 @@
+use clap::ValueEnum;
+
+/// How in-between shades are spread over neighbouring ramp glyphs and palette colors
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Every pixel takes the nearest glyph and color
+    #[default]
+    None,
+    /// Floyd–Steinberg error diffusion
+    FloydSteinberg,
+    /// Atkinson error diffusion, lighter and with more contrast
+    Atkinson,
+    /// 4x4 Bayer ordered dithering
+    Bayer4,
+    /// 8x8 Bayer ordered dithering, finer than 4x4
+    Bayer8,
+}
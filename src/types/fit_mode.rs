@@ -0,0 +1,13 @@
+use clap::ValueEnum;
+
+/// How the source is mapped onto the output grid when both dimensions are bounded
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FitMode {
+    /// Keep the aspect ratio and letterbox the remaining space
+    #[default]
+    Fit,
+    /// Keep the aspect ratio and crop whatever overflows
+    Fill,
+    /// Use the whole grid, distorting the image
+    Stretch,
+}
@@ -0,0 +1,12 @@
+use crate::source::Picture;
+
+/// Decoded frame handed from the decoder to a conversion worker
+#[derive(Debug)]
+pub struct FrameJob {
+    /// Frame the worker scales, lays out and renders, or text to store as it is
+    pub picture: Picture,
+    /// Position on the output timeline, assigned by the decoder to keep numbering stable
+    pub slot: u64,
+    /// Index of the decoded video frame, used in diagnostics
+    pub video_frame: u64,
+}
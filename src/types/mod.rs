@@ -1,6 +1,14 @@
-pub mod cleanup_guard;
-pub mod consts;
-pub mod convert_args;
-pub mod info;
-pub mod play_args;
-pub mod terminal_guard;
+pub mod audio_format;
+pub mod auto_levels;
+pub mod charset;
+pub mod color_mode;
+pub(crate) mod consts;
+pub mod dither;
+pub mod fit_mode;
+pub(crate) mod frame_job;
+pub(crate) mod info;
+pub mod output_format;
+pub mod render_mode;
+pub mod render_options;
+pub(crate) mod terminal_guard;
+pub mod tone_options;
@@ -0,0 +1,17 @@
+use clap::ValueEnum;
+
+/// Layout `convert` writes its frames in
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One `{second}/{n}.txt` file per frame
+    #[default]
+    Dirs,
+    /// One `{n}.txt` file per frame, all in the output directory
+    Flat,
+    /// A single packed container file
+    Pack,
+    /// Frames written to standard output, separated by form feeds
+    Stdout,
+    /// An asciicast v2 recording, playable with asciinema
+    Cast,
+}
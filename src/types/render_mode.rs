@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+
+/// Glyph strategy used to turn scaled frames into text
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// One character of the density ramp per pixel
+    #[default]
+    Ascii,
+    /// `▀`/`▄` half blocks, two vertical pixels per cell
+    HalfBlock,
+    /// 2x4 braille dot patterns, eight pixels per cell
+    Braille,
+    /// Line glyphs `| / - \ _` along detected edges, the density ramp elsewhere
+    Edges,
+}
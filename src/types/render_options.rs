@@ -0,0 +1,128 @@
+use super::{
+    charset::Charset, color_mode::ColorMode, consts::DEFAULT_WIDTH, dither::Dither,
+    fit_mode::FitMode, render_mode::RenderMode, tone_options::ToneOptions,
+};
+use crate::error::{Error, Result, Warnings};
+use crate::layout::Layout;
+use sysx::utils::term::txy;
+
+/// Sizing and glyph options for anything that renders frames
+///
+/// The default matches the command line defaults: 100 cells wide, plain ASCII with the
+/// `detailed` ramp and no color.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Output ASCII art width (derived from the height and aspect ratio if omitted)
+    pub width: Option<u32>,
+
+    /// Output ASCII art height (derived from the width and aspect ratio if omitted)
+    pub height: Option<u32>,
+
+    /// Bounds the output by the terminal size when neither width nor height is given
+    pub auto_size: bool,
+
+    /// How to map the image onto the output when both dimensions are bounded
+    pub fit: FitMode,
+
+    /// Height of a terminal character cell divided by its width
+    pub cell_aspect: f64,
+
+    /// Color escape sequences to embed in every frame
+    pub color: ColorMode,
+
+    /// Glyph strategy used to draw each frame
+    pub mode: RenderMode,
+
+    /// Character ramp: a preset (simple, detailed, blocks, digits, inverted) or a literal string
+    pub charset: Charset,
+
+    /// Reverses the character ramp for terminals with a light background
+    pub invert: bool,
+
+    /// Dithers between ramp glyphs, and between palette colors with 16 or 256 colors
+    pub dither: Dither,
+
+    /// Adjustments made to the pixels before glyphs are picked
+    pub tone: ToneOptions,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            auto_size: false,
+            fit: FitMode::Fit,
+            cell_aspect: 2.0,
+            color: ColorMode::None,
+            mode: RenderMode::Ascii,
+            charset: Charset::default(),
+            invert: false,
+            dither: Dither::None,
+            tone: ToneOptions::default(),
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Character ramp with [`RenderOptions::invert`] applied
+    pub fn ramp(&self) -> Charset {
+        if self.invert {
+            self.charset.clone().inverted()
+        } else {
+            self.charset.clone()
+        }
+    }
+
+    /// Lays out a `source` sized image for a renderer with `cell_size` pixels per cell,
+    /// bounded by the terminal when `auto_size` is set and no dimension was given
+    ///
+    /// Falls back to the default width with a warning when the terminal size is unknown.
+    pub(crate) fn layout(
+        &self,
+        source: (u32, u32),
+        cell_size: (u32, u32),
+        auto_size: bool,
+        warnings: &Warnings,
+    ) -> Result<Layout> {
+        let terminal = if auto_size { txy() } else { None };
+        if auto_size && terminal.is_none() && (self.width, self.height) == (None, None) {
+            warnings.warn("Could not determine terminal size, using default width");
+        }
+        self.layout_within(source, cell_size, terminal)
+    }
+
+    /// Like [`RenderOptions::layout`], bounded by a terminal of `terminal` columns and rows
+    /// instead of querying it
+    pub(crate) fn layout_within(
+        &self,
+        source: (u32, u32),
+        cell_size: (u32, u32),
+        terminal: Option<(u16, u16)>,
+    ) -> Result<Layout> {
+        if !self.cell_aspect.is_finite() || self.cell_aspect <= 0.0 {
+            return Err(Error::InvalidOption(
+                "Cell aspect must be positive".to_string(),
+            ));
+        }
+
+        let mut bounds = (self.width, self.height);
+        if bounds == (None, None) {
+            bounds = match terminal {
+                Some((term_width, term_height)) => (
+                    Some(term_width.max(1).into()),
+                    Some(term_height.max(1).into()),
+                ),
+                None => (Some(DEFAULT_WIDTH), None),
+            };
+        }
+
+        Ok(Layout::new(
+            source,
+            bounds,
+            self.fit,
+            self.cell_aspect,
+            cell_size,
+        ))
+    }
+}
@@ -8,6 +8,8 @@ impl TerminalGuard {
         let mut stdout = stdout();
         let _ = stdout.execute(terminal::EnterAlternateScreen);
         let _ = stdout.execute(cursor::Hide);
+        // Raw mode delivers key presses immediately for the playback controls
+        let _ = terminal::enable_raw_mode();
         Self
     }
 }
@@ -15,6 +17,7 @@ impl TerminalGuard {
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
         let mut stdout = stdout();
+        let _ = terminal::disable_raw_mode();
         let _ = stdout.execute(cursor::Show);
         let _ = stdout.execute(terminal::LeaveAlternateScreen);
         let _ = stdout.flush();
@@ -0,0 +1,65 @@
+use super::auto_levels::AutoLevels;
+use crate::error::{Error, Result};
+
+/// Tone adjustments applied to the pixels of every frame before glyphs are picked
+///
+/// The default leaves frames untouched. Levels are adjusted first, then brightness, contrast,
+/// gamma and saturation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneOptions {
+    /// Brightness offset, from -1.0 (everything black) to 1.0 (everything white)
+    pub brightness: f32,
+
+    /// Contrast factor around mid gray: 0 is flat gray, above 1 is punchier
+    pub contrast: f32,
+
+    /// Gamma correction: above 1 lifts shadows and midtones, below 1 darkens them
+    pub gamma: f32,
+
+    /// Saturation factor: 0 is grayscale, above 1 more vivid
+    pub saturation: f32,
+
+    /// Spreads every frame's tones over the full range, by stretching them or equalizing the
+    /// histogram
+    pub auto_levels: Option<AutoLevels>,
+}
+
+impl Default for ToneOptions {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            saturation: 1.0,
+            auto_levels: None,
+        }
+    }
+}
+
+impl ToneOptions {
+    /// Whether frames come out exactly as they went in
+    pub fn is_neutral(&self) -> bool {
+        self.brightness == 0.0
+            && self.contrast == 1.0
+            && self.gamma == 1.0
+            && self.saturation == 1.0
+            && self.auto_levels.is_none()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(Error::InvalidOption(message.to_string()));
+        if !(-1.0..=1.0).contains(&self.brightness) {
+            return invalid("Brightness must be between -1 and 1");
+        }
+        if !self.contrast.is_finite() || self.contrast < 0.0 {
+            return invalid("Contrast must not be negative");
+        }
+        if !self.gamma.is_finite() || self.gamma <= 0.0 {
+            return invalid("Gamma must be positive");
+        }
+        if !self.saturation.is_finite() || self.saturation < 0.0 {
+            return invalid("Saturation must not be negative");
+        }
+        Ok(())
+    }
+}